@@ -0,0 +1,69 @@
+//! The screens of the game: the loops reading the input, ticking the
+//! simulation and drawing it on a ratatui [`Terminal`], whatever its
+//! backend. The binary only sets the terminal up and picks the screen.
+//!
+//! [`Terminal`]: ratatui::Terminal
+
+use std::{io, time::Duration};
+
+use crossterm::{
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event},
+    ExecutableCommand,
+};
+
+mod campaign;
+mod edit;
+#[cfg(feature = "net")]
+mod online;
+mod play;
+mod versus;
+mod watch;
+
+pub use campaign::play_campaign;
+pub use edit::edit;
+#[cfg(feature = "net")]
+pub use online::{join, spectate};
+pub use play::{play, PlayMode};
+pub use versus::versus;
+pub use watch::watch;
+
+/// Where the screens read the keys, the mouse and the resizes from.
+pub trait EventSource {
+    /// The next event if one comes within `timeout`.
+    fn poll(&mut self, timeout: Duration) -> io::Result<Option<Event>>;
+
+    /// Waits for the next event.
+    fn read(&mut self) -> io::Result<Event>;
+
+    /// Starts or stops reporting the mouse, only the editor uses it.
+    fn capture_mouse(&mut self, _capture: bool) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The events of the terminal the process runs in.
+pub struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn poll(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        if event::poll(timeout)? {
+            event::read().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        event::read()
+    }
+
+    fn capture_mouse(&mut self, capture: bool) -> io::Result<()> {
+        if capture {
+            io::stdout().execute(EnableMouseCapture)?;
+        } else {
+            io::stdout().execute(DisableMouseCapture)?;
+        }
+
+        Ok(())
+    }
+}
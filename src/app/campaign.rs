@@ -0,0 +1,65 @@
+use std::{io, path::Path};
+
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{backend::Backend, Terminal};
+
+use super::{play, EventSource, PlayMode};
+use crate::{
+    campaign::{self, Progress},
+    config::Config,
+    engine::Engine,
+    game::Settings,
+    ui,
+};
+
+/// Shows the stages of the campaign and plays the ones picked until the
+/// player quits, keeping the progress made.
+pub fn play_campaign<B: Backend>(
+    terminal: &mut Terminal<B>,
+    input: &mut impl EventSource,
+    config: &Config,
+    config_path: Option<&Path>,
+    settings: Settings,
+) -> io::Result<Vec<String>> {
+    let stages = campaign::stages();
+    // an unreadable progress file shouldn't prevent playing, it starts over
+    let mut progress = Progress::load().unwrap_or_default();
+    let mut selected = progress.next_stage(&stages);
+    let mut messages = Vec::new();
+
+    loop {
+        terminal.draw(|frame| ui::draw_level_select(frame, &stages, &progress, selected))?;
+
+        let Event::Key(key) = input.read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => selected = selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                selected = (selected + 1).min(stages.len() - 1);
+            }
+            KeyCode::Enter if progress.is_unlocked(&stages, selected) => {
+                let stage = &stages[selected];
+                let mut engine =
+                    Engine::with_level(stage.level.clone(), settings.clone(), rand::random());
+                engine.start();
+
+                let mode = PlayMode::Campaign {
+                    stage,
+                    progress: &mut progress,
+                };
+                messages.extend(play(terminal, input, config, config_path, engine, mode)?);
+
+                selected = progress.next_stage(&stages);
+            }
+            KeyCode::Char('q') | KeyCode::Esc => break,
+            _ => {}
+        }
+    }
+
+    Ok(messages)
+}
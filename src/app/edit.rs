@@ -0,0 +1,122 @@
+use std::{io, path::Path};
+
+use crossterm::event::{Event, KeyCode, KeyEventKind, MouseButton, MouseEventKind};
+use ratatui::{backend::Backend, Terminal};
+
+use super::{play, EventSource, PlayMode};
+use crate::{
+    config::Config,
+    editor::{Brush, Editor},
+    engine::Engine,
+    game::{Direction, Settings},
+    ui,
+};
+
+/// Asks to confirm quitting the editor with changes not saved yet.
+const UNSAVED: &str = "The level has unsaved changes, press Q again to quit without saving";
+
+/// Edits the level that will be saved to `path`, the level being test
+/// played with `settings` on demand.
+pub fn edit<B: Backend>(
+    terminal: &mut Terminal<B>,
+    input: &mut impl EventSource,
+    config: &Config,
+    config_path: Option<&Path>,
+    settings: Settings,
+    path: &Path,
+    mut editor: Editor,
+) -> io::Result<Vec<String>> {
+    let theme = config.theme();
+    let mut messages = Vec::new();
+
+    input.capture_mouse(true)?;
+
+    loop {
+        terminal.draw(|frame| ui::draw_editor(frame, &editor, &theme))?;
+
+        let area = terminal.get_frame().size();
+        let key = match input.read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            Event::Mouse(mouse) => {
+                let brush = match mouse.kind {
+                    MouseEventKind::Down(MouseButton::Left)
+                    | MouseEventKind::Drag(MouseButton::Left) => editor.brush,
+                    MouseEventKind::Down(MouseButton::Right)
+                    | MouseEventKind::Drag(MouseButton::Right) => Brush::Erase,
+                    _ => continue,
+                };
+
+                if let Some(cell) = ui::cell_at(area, editor.board, &theme, mouse.column, mouse.row)
+                {
+                    editor.cursor = cell;
+                    editor.paint(cell, brush);
+                }
+                continue;
+            }
+            _ => continue,
+        };
+
+        // a message is shown until the next key press
+        let quitting = editor
+            .message
+            .take()
+            .is_some_and(|message| message == UNSAVED);
+
+        match key.code {
+            KeyCode::Up => editor.move_cursor(Direction::Up),
+            KeyCode::Down => editor.move_cursor(Direction::Down),
+            KeyCode::Left => editor.move_cursor(Direction::Left),
+            KeyCode::Right => editor.move_cursor(Direction::Right),
+            KeyCode::Char(' ') => editor.paint(editor.cursor, editor.brush),
+            KeyCode::Char('w') => editor.brush = Brush::Wall,
+            KeyCode::Char('f') => editor.brush = Brush::Food,
+            KeyCode::Char('e') => editor.brush = Brush::Erase,
+            KeyCode::Char('d') => editor.drawing = !editor.drawing,
+            KeyCode::Char('p') => editor.place_spawn(),
+            KeyCode::Char('r') => editor.rotate_spawn(),
+            KeyCode::Char('t') => {
+                let level = editor.level();
+
+                match level.check_fits(settings.starting_length) {
+                    Ok(()) => {
+                        let mut engine =
+                            Engine::with_level(level, settings.clone(), rand::random());
+                        engine.start();
+
+                        input.capture_mouse(false)?;
+                        messages.extend(play(
+                            terminal,
+                            input,
+                            config,
+                            config_path,
+                            engine,
+                            PlayMode::Test,
+                        )?);
+                        input.capture_mouse(true)?;
+                    }
+                    Err(message) => editor.message = Some(message),
+                }
+            }
+            KeyCode::Char('s') => {
+                editor.message = Some(match editor.level().save(path) {
+                    Ok(()) => {
+                        editor.modified = false;
+                        format!("Saved to {}", path.display())
+                    }
+                    Err(message) => message,
+                });
+            }
+            KeyCode::Char('q') | KeyCode::Esc => {
+                if !editor.modified || quitting {
+                    break;
+                }
+                editor.message = Some(UNSAVED.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    input.capture_mouse(false)?;
+
+    Ok(messages)
+}
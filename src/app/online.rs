@@ -0,0 +1,176 @@
+use std::{io, time::Duration};
+
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{backend::Backend, Terminal};
+
+use super::EventSource;
+use crate::{
+    arena::{Arena, ArenaState},
+    config::Config,
+    keys::Action,
+    net::{ClientMessage, Connection, ServerMessage, Welcome},
+    ui,
+};
+
+/// How long the client of a match over the network waits for a key before
+/// looking for news from the server.
+const NET_POLL: Duration = Duration::from_millis(10);
+
+/// Plays the match of the server `connection` is connected to, until the
+/// player quits or the server goes away.
+pub fn join<B: Backend>(
+    terminal: &mut Terminal<B>,
+    input: &mut impl EventSource,
+    config: &Config,
+    mut connection: Connection,
+    welcome: Welcome,
+) -> io::Result<Vec<String>> {
+    let theme = config.theme();
+    let Welcome {
+        player: Some(player),
+        input_delay,
+        mut arena,
+    } = welcome
+    else {
+        return Ok(vec!["The server only let us spectate".to_string()]);
+    };
+    let mut frame = 0;
+    let mut connected = Vec::new();
+
+    loop {
+        loop {
+            match connection.receive() {
+                Ok(Some(ServerMessage::Snapshot(snapshot))) => {
+                    snapshot.apply(&mut arena);
+                    frame = snapshot.frame;
+                    connected = snapshot.connected;
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(error) => return Ok(vec![format!("{error}")]),
+            }
+        }
+
+        terminal.draw(|frame| {
+            ui::draw_arena(frame, &arena, &theme);
+            ui::draw_status(frame, &join_status(&arena, player, &connected));
+        })?;
+
+        let Some(Event::Key(key)) = input.poll(NET_POLL)? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        let message = match config.keys.action(key) {
+            Some(Action::Quit) => break,
+            Some(action) => action.direction().map(|direction| ClientMessage::Turn {
+                frame: frame + input_delay,
+                direction,
+            }),
+            None if key.code == KeyCode::Char(' ') && arena.state == ArenaState::Startup => {
+                Some(ClientMessage::Start)
+            }
+            None => None,
+        };
+        if let Some(message) = message {
+            if connection.send(&message).is_err() {
+                return Ok(vec!["Lost the connection to the server".to_string()]);
+            }
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+/// Watches the match of the server `connection` is connected to, until the
+/// spectator quits or the server goes away.
+pub fn spectate<B: Backend>(
+    terminal: &mut Terminal<B>,
+    input: &mut impl EventSource,
+    config: &Config,
+    connection: Connection,
+    mut arena: Arena,
+) -> io::Result<Vec<String>> {
+    let theme = config.theme();
+    let players = arena.players.len();
+    let mut followed = 0;
+    let mut connected = Vec::new();
+
+    loop {
+        loop {
+            match connection.receive() {
+                Ok(Some(ServerMessage::Snapshot(snapshot))) => {
+                    snapshot.apply(&mut arena);
+                    connected = snapshot.connected;
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(error) => return Ok(vec![format!("{error}")]),
+            }
+        }
+
+        terminal.draw(|frame| {
+            ui::draw_spectator(frame, &arena, &theme, followed, &connected);
+            ui::draw_status(
+                frame,
+                &format!(
+                    " Following player {}  |  Tab or arrows to switch, 1-{players} to pick  |  Q to quit",
+                    followed + 1
+                ),
+            );
+        })?;
+
+        let Some(Event::Key(key)) = input.poll(NET_POLL)? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        // the players are numbered from 1 on screen
+        let picked = match key.code {
+            KeyCode::Char(c) => c
+                .to_digit(10)
+                .and_then(|n| (n as usize).checked_sub(1))
+                .filter(|&player| player < players),
+            _ => None,
+        };
+
+        match (key.code, picked) {
+            (_, Some(player)) => followed = player,
+            (KeyCode::Tab | KeyCode::Right | KeyCode::Down, _) => {
+                followed = (followed + 1) % players;
+            }
+            (KeyCode::BackTab | KeyCode::Left | KeyCode::Up, _) => {
+                followed = (followed + players - 1) % players;
+            }
+            _ if config.keys.action(key) == Some(Action::Quit) => break,
+            _ => {}
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+/// Who the player of a match over the network is and what is going on.
+fn join_status(arena: &Arena, player: usize, connected: &[bool]) -> String {
+    let you = format!(" You are player {}", player + 1);
+
+    match arena.state {
+        ArenaState::Startup => format!(
+            "{you}. Waiting for players ({}/{}), press space to start now",
+            connected.iter().filter(|&&connected| connected).count(),
+            arena.players.len()
+        ),
+        ArenaState::Running | ArenaState::Paused
+            if !arena.players.get(player).is_some_and(|player| player.alive) =>
+        {
+            format!("{you}. You are out, wait for the next round")
+        }
+        ArenaState::RoundOver { .. } => format!("{you}. The next round starts in a few seconds"),
+        ArenaState::MatchOver { .. } => format!("{you}. A new match starts in a few seconds"),
+        ArenaState::Running | ArenaState::Paused => format!("{you}. Q to quit"),
+    }
+}
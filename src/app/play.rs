@@ -0,0 +1,316 @@
+use std::{io, path::Path, sync::Mutex};
+
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{backend::Backend, Terminal};
+
+use super::EventSource;
+use crate::{
+    bot,
+    campaign::{Par, Progress, Stage},
+    config::{self, Config},
+    engine::Engine,
+    game::{Direction, GameState},
+    keys::{Action, Remapper},
+    replay::Recorder,
+    save::SavedGame,
+    scores::{HighScores, Score, MAX_NAME_LENGTH},
+    timestep::FixedTimestep,
+    ui,
+};
+
+/// Who plays in [`play`] and what is kept of the finished games, on top of
+/// their replays.
+pub enum PlayMode<'a> {
+    /// The player plays, good scores enter the high score table.
+    Player,
+    /// The [`bot`] plays.
+    Bot,
+    /// The player plays `stage` of the campaign, the results are kept in
+    /// `progress`.
+    Campaign {
+        stage: &'a Stage,
+        progress: &'a mut Progress,
+    },
+    /// The player tries the level being edited.
+    Test,
+    /// The player of an SSH session. Nothing is saved but the scores, kept
+    /// under `user` in the table the sessions share.
+    Remote {
+        user: &'a str,
+        high_scores: &'a Mutex<HighScores>,
+    },
+}
+
+/// Plays games on `engine` until the player quits, and returns the messages
+/// to show once the terminal is restored, like where the replays were saved.
+/// Keys remapped in game are written to the config file at `config_path`.
+pub fn play<B: Backend>(
+    terminal: &mut Terminal<B>,
+    input: &mut impl EventSource,
+    config: &Config,
+    config_path: Option<&Path>,
+    mut engine: Engine,
+    mut mode: PlayMode,
+) -> io::Result<Vec<String>> {
+    let autopilot = matches!(mode, PlayMode::Bot);
+    let theme = config.theme();
+    let mut bindings = config.keys.clone();
+    // the key remapping screen, over the startup or the pause screen
+    let mut remapper: Option<Remapper> = None;
+    let keeps_replays = !matches!(mode, PlayMode::Remote { .. });
+    let mut recorder = keeps_replays.then(|| Recorder::new(&engine));
+    let mut messages = Vec::new();
+    let can_continue = matches!(mode, PlayMode::Player | PlayMode::Bot) && SavedGame::exists();
+    // why the saved game couldn't be continued
+    let mut save_error: Option<String> = None;
+    let local_scores;
+    let high_scores = match &mode {
+        PlayMode::Remote { high_scores, .. } => *high_scores,
+        _ => {
+            // an unreadable table shouldn't prevent playing, it starts over
+            // empty
+            local_scores = Mutex::new(HighScores::load().unwrap_or_default());
+            &local_scores
+        }
+    };
+    // the name being typed after a new high score
+    let mut name: Option<String> = None;
+
+    let mut timestep = FixedTimestep::new(engine.ticks_per_second());
+
+    loop {
+        for _ in 0..timestep.ticks() {
+            if autopilot {
+                if let Some(direction) = bot::next_turn(&engine) {
+                    engine.queue_turn(direction);
+                }
+            }
+
+            let events = engine.tick();
+
+            if let Some(recorder) = recorder.as_mut() {
+                recorder.record(&engine, &events);
+            }
+
+            if events.level_up || events.slowed || events.power_ups_changed {
+                timestep.set_ticks_per_second(engine.ticks_per_second());
+            }
+
+            if events.game_over || events.won {
+                match &mut mode {
+                    PlayMode::Player if is_high_score(high_scores, &engine) => {
+                        name = Some(String::new());
+                    }
+                    PlayMode::Campaign { progress, .. } => {
+                        progress.update(&engine);
+                        // like high scores, progress that can't be saved is
+                        // only kept for this session
+                        let _ = progress.save();
+                    }
+                    PlayMode::Remote { user, .. } if is_high_score(high_scores, &engine) => {
+                        record_score(high_scores, &engine, user);
+                    }
+                    _ => {}
+                }
+
+                if let Some(recorder) = recorder.take() {
+                    // a failing disk must not stop the game, the replay is
+                    // just lost
+                    if let Ok(path) = recorder.finish(&engine).save_to_replays_dir() {
+                        messages.push(format!("Replay saved to {}", path.display()));
+                    }
+                }
+            }
+        }
+
+        terminal.draw(|frame| {
+            ui::draw(frame, &engine, &theme);
+
+            if engine.game.state == GameState::Startup {
+                if let Ok(high_scores) = high_scores.lock() {
+                    ui::draw_high_scores(frame, &high_scores);
+                }
+
+                if let Some(error) = &save_error {
+                    ui::draw_status(
+                        frame,
+                        &format!(" Could not continue the saved game: {error}"),
+                    );
+                } else if can_continue {
+                    ui::draw_status(frame, " Press C to continue your saved game");
+                }
+            }
+            if let PlayMode::Campaign { stage, .. } = &mode {
+                ui::draw_status(frame, &stage_status(&engine, stage));
+            }
+            if let Some(remapper) = &remapper {
+                ui::draw_remap(frame, remapper, &bindings);
+            }
+            if let Some(name) = &name {
+                ui::draw_status(
+                    frame,
+                    &format!(" New high score! Your name: {name}_ (enter to save, esc to skip)"),
+                );
+            }
+        })?;
+
+        // wait for input only until the next tick is due, so key presses
+        // never change how fast the game goes
+        if let Some(Event::Key(key)) = input.poll(timestep.until_next_tick())? {
+            let interrupted =
+                key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+
+            if interrupted {
+                break;
+            }
+            if key.kind == KeyEventKind::Press {
+                if let Some(typed) = name.as_mut() {
+                    match key.code {
+                        KeyCode::Char(c) if typed.chars().count() < MAX_NAME_LENGTH => {
+                            typed.push(c);
+                        }
+                        KeyCode::Backspace => {
+                            typed.pop();
+                        }
+                        KeyCode::Enter => {
+                            let typed = name.take().unwrap_or_default();
+                            record_score(high_scores, &engine, &typed);
+                        }
+                        KeyCode::Esc => name = None,
+                        _ => {}
+                    }
+                } else if let Some(screen) = remapper.as_mut() {
+                    if !screen.handle_key(key, &mut bindings) {
+                        remapper = None;
+
+                        if let Some(path) = config_path {
+                            if let Err(message) = config::save_key_bindings(path, &bindings) {
+                                messages.push(format!("Could not save the keys: {message}"));
+                            }
+                        }
+                    }
+                } else if bindings.action(key) == Some(Action::Remap)
+                    && matches!(engine.game.state, GameState::Startup | GameState::Paused)
+                {
+                    remapper = Some(Remapper::default());
+                } else if engine.game.state == GameState::Startup {
+                    let continuing =
+                        can_continue && save_error.is_none() && key.code == KeyCode::Char('c');
+
+                    match continuing.then(SavedGame::take) {
+                        Some(Ok(Some(saved))) => {
+                            engine = saved.engine;
+                            recorder = saved.recorder;
+                            timestep.set_ticks_per_second(engine.ticks_per_second());
+                        }
+                        // the next key plays a new game
+                        Some(Err(error)) => save_error = Some(error.to_string()),
+                        Some(Ok(None)) | None => engine.start(),
+                    }
+                } else {
+                    match bindings.action(key) {
+                        Some(Action::Quit) => {
+                            // stages and levels being edited are played
+                            // from their own screen, they can't be
+                            // continued from the startup screen
+                            if engine.game.state == GameState::Paused
+                                && matches!(mode, PlayMode::Player | PlayMode::Bot)
+                            {
+                                match SavedGame::new(engine, recorder).save() {
+                                    Ok(()) => messages.push(
+                                        "Game saved, continue it from the startup screen"
+                                            .to_string(),
+                                    ),
+                                    Err(error) => {
+                                        messages.push(format!("Could not save the game: {error}"))
+                                    }
+                                }
+                            }
+
+                            break;
+                        }
+                        Some(Action::Pause) => {
+                            if engine.game.state == GameState::Paused {
+                                engine.game.state = GameState::Running;
+                            } else if GameState::Running == engine.game.state {
+                                engine.game.state = GameState::Paused;
+                            }
+                        }
+                        Some(Action::TurnLeft) => engine.queue_turn(Direction::Left),
+                        Some(Action::TurnRight) => engine.queue_turn(Direction::Right),
+                        Some(Action::TurnUp) => engine.queue_turn(Direction::Up),
+                        Some(Action::TurnDown) => engine.queue_turn(Direction::Down),
+                        Some(Action::Restart)
+                            if matches!(
+                                engine.game.state,
+                                GameState::GameOver | GameState::Won
+                            ) =>
+                        {
+                            engine.restart();
+                            recorder = keeps_replays.then(|| Recorder::new(&engine));
+                            timestep.set_ticks_per_second(engine.ticks_per_second());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(messages)
+}
+
+/// Whether the game `engine` just finished enters `high_scores`.
+fn is_high_score(high_scores: &Mutex<HighScores>, engine: &Engine) -> bool {
+    high_scores
+        .lock()
+        .is_ok_and(|high_scores| high_scores.is_high_score(engine.game.score))
+}
+
+/// Enters the game `engine` just finished in `high_scores` under `name`.
+/// The sessions of an SSH server take turns to change and save their table.
+fn record_score(high_scores: &Mutex<HighScores>, engine: &Engine, name: &str) {
+    let Ok(mut high_scores) = high_scores.lock() else {
+        return;
+    };
+
+    high_scores.insert(Score::new(engine, name));
+    // like replays, a score that can't be saved is only kept for this
+    // session
+    let _ = high_scores.save();
+}
+
+/// The goal of `stage` and how far the game on `engine` is from it.
+fn stage_status(engine: &Engine, stage: &Stage) -> String {
+    let time = engine.elapsed_ms / 1000;
+
+    match (&engine.game.state, stage.goal()) {
+        (GameState::Won, _) => {
+            let result = match stage.par {
+                Par::Time(_) => format!("in {}", ui::duration(time)),
+                Par::Score(_) => format!("with {} pts", engine.game.score),
+            };
+            let verdict = if stage.par.is_met(engine) {
+                "within par"
+            } else {
+                "short of par"
+            };
+
+            format!(
+                " Level cleared {result}, {verdict} ({})! R to play again, Q for the level select",
+                stage.par
+            )
+        }
+        (GameState::GameOver, Some(goal)) => {
+            format!(" Goal: {goal}  |  R to try again, Q for the level select")
+        }
+        (_, Some(goal)) => format!(
+            " {}  Goal: {goal} ({})  Time {}  Par {}",
+            engine.level.as_ref().map_or("", |level| &level.name),
+            goal.progress(engine),
+            ui::duration(time),
+            stage.par
+        ),
+        (_, None) => String::new(),
+    }
+}
@@ -0,0 +1,81 @@
+use std::io;
+
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{backend::Backend, Terminal};
+
+use super::EventSource;
+use crate::{
+    arena::{Arena, ArenaState},
+    config::Config,
+    keys::{Action, KeyBindings},
+    timestep::FixedTimestep,
+    ui,
+};
+
+/// Plays a match between two players sharing the keyboard on `arena`, then
+/// more until they quit.
+pub fn versus<B: Backend>(
+    terminal: &mut Terminal<B>,
+    input: &mut impl EventSource,
+    config: &Config,
+    mut arena: Arena,
+) -> io::Result<Vec<String>> {
+    let theme = config.theme();
+    let mut timestep = FixedTimestep::new(arena.settings.speed);
+
+    loop {
+        for _ in 0..timestep.ticks() {
+            arena.tick();
+        }
+
+        terminal.draw(|frame| {
+            ui::draw_arena(frame, &arena, &theme);
+            ui::draw_status(frame, &versus_status(&arena, &config.keys));
+        })?;
+
+        let Some(Event::Key(key)) = input.poll(timestep.until_next_tick())? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        // the turn keys start a match like any other, they only turn once
+        // it is running
+        if arena.state == ArenaState::Running {
+            if let Some((player, action)) = config.keys.split_turn(key) {
+                if let Some(direction) = action.direction() {
+                    arena.queue_turn(player, direction);
+                }
+                continue;
+            }
+        }
+
+        match (config.keys.action(key), arena.state) {
+            (Some(Action::Quit), _) => break,
+            (_, ArenaState::Startup) => arena.state = ArenaState::Running,
+            (Some(Action::Pause), ArenaState::Running) => arena.state = ArenaState::Paused,
+            (Some(Action::Pause), ArenaState::Paused) => arena.state = ArenaState::Running,
+            (_, ArenaState::RoundOver { .. }) if key.code == KeyCode::Char(' ') => {
+                arena.next_round();
+            }
+            (Some(Action::Restart), ArenaState::MatchOver { .. }) => arena.restart(),
+            _ => {}
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+/// What the players of a local match can press.
+fn versus_status(arena: &Arena, bindings: &KeyBindings) -> String {
+    match arena.state {
+        ArenaState::Startup => format!(
+            " Player 1: {}   Player 2: arrows   Press any key to start",
+            bindings.first_player_keys()
+        ),
+        ArenaState::RoundOver { .. } => " Press space for the next round".to_string(),
+        ArenaState::MatchOver { .. } => " Press R for a new match, Q to quit".to_string(),
+        ArenaState::Running | ArenaState::Paused => String::new(),
+    }
+}
@@ -0,0 +1,132 @@
+use std::{io, time::Duration};
+
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{backend::Backend, Terminal};
+
+use super::EventSource;
+use crate::{
+    replay::{Player, Replay},
+    timestep::FixedTimestep,
+    ui::{self, Theme},
+};
+
+/// The playback speeds of a replay, relative to the recorded one.
+const REPLAY_SPEEDS: [f64; 4] = [0.5, 1.0, 2.0, 4.0];
+
+/// Plays `replay` back with pause, speed, step and seek controls.
+pub fn watch<B: Backend>(
+    terminal: &mut Terminal<B>,
+    input: &mut impl EventSource,
+    replay: Replay,
+    theme: &Theme,
+) -> io::Result<()> {
+    let mut player = Player::new(replay);
+    let mut speed = 1;
+    let mut paused = false;
+    // the tick being typed after pressing `g`
+    let mut seek_to: Option<String> = None;
+
+    let mut timestep = FixedTimestep::new(player.engine().ticks_per_second());
+
+    loop {
+        for _ in 0..timestep.ticks() {
+            if !paused {
+                player.step();
+            }
+        }
+
+        // the recorded speed changes when the snake levels up or slows
+        // down
+        timestep.set_step(Duration::from_secs_f64(
+            1.0 / (player.engine().ticks_per_second() as f64 * REPLAY_SPEEDS[speed]),
+        ));
+
+        terminal.draw(|frame| {
+            ui::draw(frame, player.engine(), theme);
+            ui::draw_status(
+                frame,
+                &replay_status(&player, REPLAY_SPEEDS[speed], paused, seek_to.as_deref()),
+            );
+        })?;
+
+        if let Some(Event::Key(key)) = input.poll(timestep.until_next_tick())? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if let Some(typed) = seek_to.as_mut() {
+                match key.code {
+                    KeyCode::Char(digit) if digit.is_ascii_digit() => typed.push(digit),
+                    KeyCode::Backspace => {
+                        typed.pop();
+                    }
+                    KeyCode::Enter => {
+                        if let Ok(tick) = typed.parse() {
+                            player.seek(tick);
+                        }
+                        seek_to = None;
+                    }
+                    KeyCode::Esc => seek_to = None,
+                    _ => {}
+                }
+
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    break;
+                }
+                KeyCode::Char(' ') | KeyCode::Char('p') => {
+                    paused = !paused;
+                }
+                KeyCode::Char('-') => {
+                    speed = speed.saturating_sub(1);
+                }
+                KeyCode::Char('+') | KeyCode::Char('=') => {
+                    speed = (speed + 1).min(REPLAY_SPEEDS.len() - 1);
+                }
+                KeyCode::Char('.') | KeyCode::Right => {
+                    paused = true;
+                    player.step();
+                }
+                KeyCode::Char(',') | KeyCode::Left => {
+                    paused = true;
+                    player.seek(player.tick().saturating_sub(1));
+                }
+                KeyCode::Char('g') => {
+                    seek_to = Some(String::new());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn replay_status(player: &Player, speed: f64, paused: bool, seek_to: Option<&str>) -> String {
+    if let Some(typed) = seek_to {
+        return format!(" Seek to tick: {typed}_ (enter to jump, esc to cancel)");
+    }
+
+    let mut status = format!(
+        " Replay  tick {}/{}  {}x",
+        player.tick(),
+        player.len(),
+        speed
+    );
+
+    if paused {
+        status.push_str("  paused");
+    } else if player.is_finished() {
+        status.push_str("  finished");
+    }
+    if let Some(tick) = player.desync() {
+        status.push_str(&format!("  DESYNC at tick {tick}"));
+    }
+
+    status.push_str("  |  space pause  -/+ speed  ,/. step  g seek  q quit");
+
+    status
+}
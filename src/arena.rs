@@ -0,0 +1,406 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{Direction, Mode, Settings, TurnQueue},
+    grid::{Board, Cell},
+    point::Point,
+    snake::Snake,
+    walls::Walls,
+};
+
+/// How many rounds a player has to win to win the match, by default.
+pub const ROUNDS_TO_WIN: u32 = 3;
+
+/// How many snakes fit on a board.
+pub const MAX_PLAYERS: usize = 8;
+
+/// The smallest board height `players` snakes fit on, each starting on its
+/// own row between the walls.
+pub fn min_height(players: usize) -> i32 {
+    players as i32 + 2
+}
+
+/// The screen a match is currently in.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum ArenaState {
+    Startup,
+    Running,
+    Paused,
+    /// The round is over, won by the last snake alive if any.
+    RoundOver {
+        winner: Option<usize>,
+    },
+    /// A player won enough rounds.
+    MatchOver {
+        winner: usize,
+    },
+}
+
+/// One of the players of a match and their snake.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Contender {
+    pub snake: Snake,
+    /// How many foods the player ate this round.
+    pub score: i32,
+    pub alive: bool,
+    /// How many rounds the player won.
+    pub wins: u32,
+    /// Turns waiting to be applied, one per tick.
+    turns: TurnQueue,
+}
+
+/// What happened during a single call to [`Arena::tick`].
+#[derive(Default)]
+pub struct ArenaEvents {
+    /// Whether the simulation moved at all, it doesn't unless running.
+    pub ticked: bool,
+    /// The players who ate the food on this tick.
+    pub ate: Vec<usize>,
+    /// The players whose snake died on this tick.
+    pub died: Vec<usize>,
+    pub round_over: bool,
+    pub match_over: bool,
+}
+
+/// A match between several snakes on the same board, played in rounds: a
+/// round ends once at most one snake is alive, the last one winning it.
+///
+/// A snake dies when its head hits a wall, its own body or another snake.
+/// When two heads meet, both die. Like the [`crate::engine::Engine`], it
+/// does not know anything about the terminal and all its randomness comes
+/// from `seed`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Arena {
+    pub board: Board,
+    pub settings: Settings,
+    pub seed: u64,
+    /// How many rounds a player has to win to win the match.
+    pub rounds_to_win: u32,
+    /// The round being played, from 1.
+    pub round: u32,
+    /// How many ticks the current round has been running for.
+    pub ticks: u64,
+    /// How long the current round has been running for, in milliseconds.
+    pub elapsed_ms: u64,
+    pub state: ArenaState,
+    pub players: Vec<Contender>,
+    /// The food to eat, `None` once the snakes filled the board.
+    pub point: Option<Point>,
+    pub walls: Walls,
+    /// How many snakes were alive when the round started: a round started
+    /// alone goes on until the snake dies.
+    #[serde(default)]
+    entrants: usize,
+    rng: ChaCha8Rng,
+}
+
+impl Arena {
+    /// Creates a match between `players` snakes on `board`, up to
+    /// [`MAX_PLAYERS`].
+    pub fn new(
+        board: Board,
+        settings: Settings,
+        players: usize,
+        rounds_to_win: u32,
+        seed: u64,
+    ) -> Self {
+        let board = board.with_wrap(settings.mode == Mode::Wrap);
+        let mut arena = Arena {
+            board,
+            walls: Walls::new(board),
+            seed,
+            rounds_to_win: rounds_to_win.max(1),
+            round: 1,
+            ticks: 0,
+            elapsed_ms: 0,
+            state: ArenaState::Startup,
+            players: Vec::new(),
+            point: None,
+            entrants: 0,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            settings,
+        };
+
+        let players = players.clamp(1, MAX_PLAYERS);
+        arena.players = (0..players)
+            .map(|i| Contender {
+                snake: arena.new_snake(i, players),
+                score: 0,
+                alive: true,
+                wins: 0,
+                turns: TurnQueue::default(),
+            })
+            .collect();
+        arena.spawn_food();
+
+        arena
+    }
+
+    /// Queues a turn of `player` to be applied on one of the next ticks, see
+    /// [`TurnQueue::push`].
+    pub fn queue_turn(&mut self, player: usize, direction: Direction) {
+        if self.state != ArenaState::Running {
+            return;
+        }
+        let Some(contender) = self.players.get_mut(player).filter(|player| player.alive) else {
+            return;
+        };
+
+        contender.turns.push(direction, contender.snake.direction);
+    }
+
+    /// Advances the simulation by one step, moving every snake alive.
+    pub fn tick(&mut self) -> ArenaEvents {
+        let mut events = ArenaEvents::default();
+
+        if self.state != ArenaState::Running {
+            return events;
+        }
+
+        if self.ticks == 0 {
+            self.entrants = self.players.iter().filter(|player| player.alive).count();
+        }
+
+        events.ticked = true;
+        self.ticks += 1;
+        self.elapsed_ms += 1000 / self.settings.speed.max(1) as u64;
+
+        for contender in self.players.iter_mut().filter(|player| player.alive) {
+            if let Some(direction) = contender.turns.pop() {
+                contender.snake.change_direction(direction);
+            }
+            contender.snake.move_snake(&self.board);
+        }
+
+        // every snake moves before any dies, so two heads meeting kill both
+        events.died = (0..self.players.len())
+            .filter(|&i| self.players[i].alive && self.collides(i))
+            .collect();
+        for &i in &events.died {
+            self.players[i].alive = false;
+        }
+
+        let head_on_food = |contender: &Contender| {
+            contender.alive
+                && self
+                    .point
+                    .as_ref()
+                    .is_some_and(|point| point.cell == contender.snake.head())
+        };
+        if let Some(i) = self.players.iter().position(head_on_food) {
+            let contender = &mut self.players[i];
+            contender.snake.grow();
+            contender.score += 1;
+            events.ate.push(i);
+
+            self.spawn_food();
+        }
+
+        let alive: Vec<usize> = (0..self.players.len())
+            .filter(|&i| self.players[i].alive)
+            .collect();
+        let last_standing = if self.entrants > 1 { 1 } else { 0 };
+
+        if alive.len() <= last_standing {
+            let winner = alive.first().copied();
+
+            events.round_over = true;
+            self.state = ArenaState::RoundOver { winner };
+
+            if let Some(winner) = winner {
+                self.players[winner].wins += 1;
+
+                if self.players[winner].wins >= self.rounds_to_win {
+                    events.match_over = true;
+                    self.state = ArenaState::MatchOver { winner };
+                }
+            }
+        }
+
+        events
+    }
+
+    /// Whether the head of `player` is on a wall, its own body or another
+    /// snake alive.
+    fn collides(&self, player: usize) -> bool {
+        let snake = &self.players[player].snake;
+        let head = snake.head();
+
+        self.walls.contains(head)
+            || snake.bites_itself()
+            || self
+                .players
+                .iter()
+                .enumerate()
+                .any(|(i, other)| i != player && other.alive && other.snake.contains(head))
+    }
+
+    /// Takes the snake of `player` out of the round, e.g. when they left.
+    pub fn eliminate(&mut self, player: usize) {
+        if let Some(contender) = self.players.get_mut(player) {
+            contender.alive = false;
+            contender.turns.clear();
+        }
+    }
+
+    /// Starts the next round once the current one is over, keeping the
+    /// rounds won.
+    pub fn next_round(&mut self) {
+        if !matches!(self.state, ArenaState::RoundOver { .. }) {
+            return;
+        }
+
+        self.round += 1;
+        self.start_round();
+        self.state = ArenaState::Running;
+    }
+
+    /// Starts a new match with the same players. Its seed is drawn from the
+    /// current RNG, like [`crate::engine::Engine::restart`].
+    pub fn restart(&mut self) {
+        self.seed = self.rng.gen();
+        self.rng = ChaCha8Rng::seed_from_u64(self.seed);
+        self.round = 1;
+        self.players.iter_mut().for_each(|player| player.wins = 0);
+        self.start_round();
+        self.state = ArenaState::Running;
+    }
+
+    /// Puts every snake back at its start and the food on the board.
+    fn start_round(&mut self) {
+        let count = self.players.len();
+
+        for i in 0..count {
+            let snake = self.new_snake(i, count);
+            let contender = &mut self.players[i];
+
+            contender.snake = snake;
+            contender.score = 0;
+            contender.alive = true;
+            contender.turns.clear();
+        }
+        self.ticks = 0;
+        self.elapsed_ms = 0;
+        self.spawn_food();
+    }
+
+    /// Places the food anywhere no snake is.
+    fn spawn_food(&mut self) {
+        let Some(first) = self.players.iter().find(|player| player.alive) else {
+            self.point = None;
+            return;
+        };
+        let taken: Vec<Cell> = self
+            .players
+            .iter()
+            .filter(|player| player.alive)
+            .flat_map(|player| player.snake.cells().copied())
+            .collect();
+
+        self.point = Point::spawn(
+            self.board,
+            &self.walls,
+            &first.snake,
+            &[],
+            &taken,
+            &mut self.rng,
+        );
+    }
+
+    /// The snake of the player `index` out of `count` at the start of a
+    /// round: each one has its own row, going right from the left edge or
+    /// left from the right edge in turn.
+    fn new_snake(&self, index: usize, count: usize) -> Snake {
+        let length = self
+            .settings
+            .starting_length
+            .min((self.board.width as usize).saturating_sub(4))
+            .max(1);
+        let y = self.board.height * (index as i32 + 1) / (count as i32 + 1);
+
+        if index.is_multiple_of(2) {
+            Snake::new(Cell::new(1 + length as i32, y), Direction::Right, length)
+        } else {
+            let x = self.board.width - 2 - length as i32;
+            Snake::new(Cell::new(x, y), Direction::Left, length)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A running match between two snakes of 3 cells, their heads on
+    /// `first` and `second`.
+    fn arena(first: (Cell, Direction), second: (Cell, Direction)) -> Arena {
+        let mut arena = Arena::new(Board::new(30, 10), Settings::default(), 2, 3, 1);
+        arena.state = ArenaState::Running;
+        arena.players[0].snake = Snake::new(first.0, first.1, 3);
+        arena.players[1].snake = Snake::new(second.0, second.1, 3);
+        arena.point = None;
+
+        arena
+    }
+
+    #[test]
+    fn heads_meeting_kill_both_snakes() {
+        let mut arena = arena(
+            (Cell::new(10, 5), Direction::Right),
+            (Cell::new(12, 5), Direction::Left),
+        );
+
+        let events = arena.tick();
+
+        assert_eq!(events.died, [0, 1]);
+        assert!(events.round_over);
+        assert_eq!(arena.state, ArenaState::RoundOver { winner: None });
+        assert!(arena.players.iter().all(|player| player.wins == 0));
+    }
+
+    #[test]
+    fn running_into_another_snake_only_kills_the_one_running() {
+        // the first snake heads into the middle of the second one
+        let mut arena = arena(
+            (Cell::new(10, 4), Direction::Up),
+            (Cell::new(11, 5), Direction::Right),
+        );
+
+        let events = arena.tick();
+
+        assert_eq!(events.died, [0]);
+        assert_eq!(arena.state, ArenaState::RoundOver { winner: Some(1) });
+        assert_eq!(arena.players[1].wins, 1);
+    }
+
+    #[test]
+    fn every_snake_starts_on_its_own_row() {
+        for players in 1..=MAX_PLAYERS {
+            let board = Board::new(30, min_height(players));
+            let arena = Arena::new(board, Settings::default(), players, 3, 1);
+            let mut rows: Vec<i32> = arena
+                .players
+                .iter()
+                .map(|player| player.snake.head().y)
+                .collect();
+
+            rows.dedup();
+            assert_eq!(rows.len(), players);
+            assert!(rows.iter().all(|&y| y > 0 && y < board.height - 1));
+        }
+    }
+
+    #[test]
+    fn winning_enough_rounds_wins_the_match() {
+        let mut arena = arena(
+            (Cell::new(10, 4), Direction::Up),
+            (Cell::new(11, 5), Direction::Right),
+        );
+        arena.players[1].wins = arena.rounds_to_win - 1;
+
+        assert!(arena.tick().match_over);
+        assert_eq!(arena.state, ArenaState::MatchOver { winner: 1 });
+    }
+}
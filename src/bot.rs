@@ -0,0 +1,76 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::{engine::Engine, game::Direction, grid::Cell, point::FoodKind};
+
+/// Picks the turn the snake of `engine` should take on the next tick: the
+/// first step of the shortest path to the food or, when the food can't be
+/// reached, the safe move leaving the most room to the snake. `None` means
+/// going straight.
+pub fn next_turn(engine: &Engine) -> Option<Direction> {
+    let snake = &engine.snake;
+    let direction = path_to_food(engine).or_else(|| roomiest_move(engine))?;
+
+    (direction != snake.direction).then_some(direction)
+}
+
+fn is_free(engine: &Engine, cell: Cell) -> bool {
+    let poisoned = engine
+        .special
+        .as_ref()
+        .is_some_and(|special| special.kind == FoodKind::Poison && special.cell == cell);
+
+    !engine.walls.contains(cell) && !engine.snake.contains(cell) && !poisoned
+}
+
+/// The moves the snake can make without dying on the next tick.
+fn safe_moves(engine: &Engine) -> impl Iterator<Item = Direction> + '_ {
+    let snake = &engine.snake;
+
+    Direction::ALL.into_iter().filter(move |&direction| {
+        direction != snake.direction.opposite()
+            && is_free(engine, engine.board.neighbour(snake.head(), direction))
+    })
+}
+
+fn path_to_food(engine: &Engine) -> Option<Direction> {
+    let target = engine.point.as_ref()?.cell;
+    // the first move taken to reach every visited cell
+    let mut first_moves = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for direction in safe_moves(engine) {
+        let cell = engine.board.neighbour(engine.snake.head(), direction);
+        first_moves.insert(cell, direction);
+        queue.push_back(cell);
+    }
+
+    while let Some(cell) = queue.pop_front() {
+        let first_move = first_moves[&cell];
+
+        if cell == target {
+            return Some(first_move);
+        }
+
+        for direction in Direction::ALL {
+            let next = engine.board.neighbour(cell, direction);
+
+            if is_free(engine, next) && !first_moves.contains_key(&next) {
+                first_moves.insert(next, first_move);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+fn roomiest_move(engine: &Engine) -> Option<Direction> {
+    safe_moves(engine).max_by_key(|&direction| {
+        let start = engine.board.neighbour(engine.snake.head(), direction);
+
+        engine
+            .board
+            .flood_fill(start, |cell| !is_free(engine, cell))
+            .len()
+    })
+}
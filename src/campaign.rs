@@ -0,0 +1,305 @@
+use std::{collections::HashMap, fmt, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::Engine,
+    game::GameState,
+    level::{Goal, Level},
+    storage, ui,
+};
+
+/// How long the snake is at the start of every stage, short enough for the
+/// smallest rooms.
+pub const STARTING_LENGTH: usize = 5;
+
+/// The built-in levels, in the order they are unlocked: name, map, goal and
+/// par.
+const STAGES: [(&str, &str, Goal, Par); 5] = [
+    (
+        "box",
+        include_str!("../levels/campaign/01-box.txt"),
+        Goal::Eat(10),
+        Par::Time(40),
+    ),
+    (
+        "pillars",
+        include_str!("../levels/campaign/02-pillars.txt"),
+        Goal::Eat(15),
+        Par::Time(60),
+    ),
+    (
+        "corridors",
+        include_str!("../levels/campaign/03-corridors.txt"),
+        Goal::Length(25),
+        Par::Time(90),
+    ),
+    (
+        "cross",
+        include_str!("../levels/campaign/04-cross.txt"),
+        Goal::Survive(60),
+        Par::Score(12),
+    ),
+    (
+        "maze",
+        include_str!("../levels/campaign/05-maze.txt"),
+        Goal::Eat(20),
+        Par::Time(150),
+    ),
+];
+
+/// What clearing a stage well takes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Par {
+    /// Clearing it within this many seconds.
+    Time(u64),
+    /// Scoring at least this much, for the stages lasting a set time.
+    Score(i32),
+}
+
+impl Par {
+    /// Whether the game on `engine` is within par.
+    pub fn is_met(self, engine: &Engine) -> bool {
+        match self {
+            Par::Time(seconds) => engine.elapsed_ms / 1000 <= seconds,
+            Par::Score(score) => engine.game.score >= score,
+        }
+    }
+}
+
+impl fmt::Display for Par {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Par::Time(seconds) => f.write_str(&ui::duration(*seconds)),
+            Par::Score(score) => write!(f, "{score} pts"),
+        }
+    }
+}
+
+/// A level of the campaign, its goal set.
+pub struct Stage {
+    pub level: Level,
+    pub par: Par,
+}
+
+impl Stage {
+    pub fn goal(&self) -> Option<Goal> {
+        self.level.goal
+    }
+}
+
+/// The stages of the campaign, in order.
+pub fn stages() -> Vec<Stage> {
+    STAGES
+        .iter()
+        .map(|&(name, map, goal, par)| {
+            let mut level =
+                Level::parse(name.to_string(), map).expect("the built-in levels are valid");
+            level.goal = Some(goal);
+
+            Stage { level, par }
+        })
+        .collect()
+}
+
+/// The best results on a stage of the campaign.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct StageRecord {
+    pub cleared: bool,
+    pub best_score: i32,
+    /// The fastest clear, in seconds.
+    pub best_time: Option<u64>,
+}
+
+/// How far the player got in the campaign, by level name.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Progress {
+    pub stages: HashMap<String, StageRecord>,
+}
+
+impl Progress {
+    /// Loads the progress from [`progress_path`], none if there is none yet.
+    pub fn load() -> io::Result<Self> {
+        Ok(storage::load_json(&progress_path()?)?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        storage::save_json(&progress_path()?, self)
+    }
+
+    pub fn record(&self, stage: &Stage) -> Option<&StageRecord> {
+        self.stages.get(&stage.level.name)
+    }
+
+    pub fn is_cleared(&self, stage: &Stage) -> bool {
+        self.record(stage).is_some_and(|record| record.cleared)
+    }
+
+    /// Whether the stage at `index` can be played: the first one always
+    /// can, the others once the previous one is cleared.
+    pub fn is_unlocked(&self, stages: &[Stage], index: usize) -> bool {
+        index == 0
+            || stages
+                .get(index - 1)
+                .is_some_and(|stage| self.is_cleared(stage))
+    }
+
+    /// The first stage not cleared yet, the last one if they all are.
+    pub fn next_stage(&self, stages: &[Stage]) -> usize {
+        stages
+            .iter()
+            .position(|stage| !self.is_cleared(stage))
+            .unwrap_or(stages.len().saturating_sub(1))
+    }
+
+    /// Keeps the outcome of the game `engine` just finished on one of the
+    /// stages.
+    pub fn update(&mut self, engine: &Engine) {
+        let Some(level) = &engine.level else {
+            return;
+        };
+        let record = self.stages.entry(level.name.clone()).or_default();
+
+        record.best_score = record.best_score.max(engine.game.score);
+
+        if engine.game.state == GameState::Won {
+            let time = engine.elapsed_ms / 1000;
+
+            record.cleared = true;
+            record.best_time = Some(record.best_time.map_or(time, |best| best.min(time)));
+        }
+    }
+}
+
+/// Where the progress in the campaign is stored.
+pub fn progress_path() -> io::Result<PathBuf> {
+    Ok(storage::data_dir()?.join("campaign.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game::Settings, point::Point};
+
+    fn engine(stage: &Stage) -> Engine {
+        let settings = Settings {
+            starting_length: STARTING_LENGTH,
+            ..Settings::default()
+        };
+        let mut engine = Engine::with_level(stage.level.clone(), settings, 1);
+        engine.start();
+
+        engine
+    }
+
+    /// `engine` finished after `seconds`, won or lost.
+    fn finish(engine: &mut Engine, won: bool, seconds: u64) {
+        engine.elapsed_ms = seconds * 1000;
+        engine.game.state = if won {
+            GameState::Won
+        } else {
+            GameState::GameOver
+        };
+    }
+
+    #[test]
+    fn clearing_a_stage_unlocks_the_next() {
+        let stages = stages();
+        let mut progress = Progress::default();
+        assert!(progress.is_unlocked(&stages, 0));
+        assert!(!progress.is_unlocked(&stages, 1));
+
+        let mut engine = engine(&stages[0]);
+        engine.game.score = 4;
+        finish(&mut engine, false, 20);
+        progress.update(&engine);
+
+        assert!(!progress.is_unlocked(&stages, 1));
+        assert_eq!(progress.record(&stages[0]).unwrap().best_score, 4);
+        assert_eq!(progress.next_stage(&stages), 0);
+
+        finish(&mut engine, true, 30);
+        progress.update(&engine);
+
+        assert!(progress.is_cleared(&stages[0]));
+        assert!(progress.is_unlocked(&stages, 1));
+        assert!(!progress.is_unlocked(&stages, 2));
+        assert_eq!(progress.next_stage(&stages), 1);
+    }
+
+    #[test]
+    fn keeps_the_best_time_against_par() {
+        let stages = stages();
+        let stage = &stages[0];
+        let Par::Time(par) = stage.par else {
+            panic!("the first stage has a time par");
+        };
+        let mut progress = Progress::default();
+        let mut engine = engine(stage);
+
+        finish(&mut engine, true, par + 10);
+        progress.update(&engine);
+        assert!(!stage.par.is_met(&engine));
+
+        finish(&mut engine, true, par - 5);
+        progress.update(&engine);
+        assert!(stage.par.is_met(&engine));
+
+        // a slower clear or a lost game doesn't replace it
+        finish(&mut engine, true, par);
+        progress.update(&engine);
+        finish(&mut engine, false, 1);
+        progress.update(&engine);
+
+        assert_eq!(progress.record(stage).unwrap().best_time, Some(par - 5));
+    }
+
+    #[test]
+    fn a_score_par_is_met_with_enough_points() {
+        let mut engine = engine(&stages()[0]);
+        engine.game.score = 11;
+
+        assert!(!Par::Score(12).is_met(&engine));
+        engine.game.score = 12;
+        assert!(Par::Score(12).is_met(&engine));
+    }
+
+    #[test]
+    fn reaches_every_kind_of_goal() {
+        let mut engine = engine(&stages()[0]);
+
+        engine.game.foods = 9;
+        assert!(!Goal::Eat(10).is_reached(&engine));
+        engine.game.foods = 10;
+        assert!(Goal::Eat(10).is_reached(&engine));
+
+        let length = engine.snake.len();
+        assert!(Goal::Length(length).is_reached(&engine));
+        assert!(!Goal::Length(length + 1).is_reached(&engine));
+
+        engine.elapsed_ms = 59_999;
+        assert!(!Goal::Survive(60).is_reached(&engine));
+        engine.elapsed_ms = 60_000;
+        assert!(Goal::Survive(60).is_reached(&engine));
+    }
+
+    #[test]
+    fn reaching_the_goal_clears_the_stage() {
+        let stages = stages();
+        let mut engine = engine(&stages[0]);
+        let Some(Goal::Eat(foods)) = stages[0].goal() else {
+            panic!("the first stage is about eating");
+        };
+        engine.game.foods = foods - 1;
+        let ahead = engine
+            .board
+            .neighbour(engine.snake.head(), engine.snake.direction);
+        engine.point = Some(Point::new(ahead));
+        engine.special = None;
+
+        let events = engine.tick();
+
+        assert!(events.won);
+        assert!(engine.game.state == GameState::Won);
+    }
+}
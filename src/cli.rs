@@ -0,0 +1,324 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+#[cfg(feature = "ssh")]
+use ratatui_snake::ssh;
+#[cfg(feature = "net")]
+use ratatui_snake::{
+    arena::MAX_PLAYERS,
+    net::{DEFAULT_PORT, INPUT_DELAY},
+};
+use ratatui_snake::{
+    arena::{self, ROUNDS_TO_WIN},
+    config::{Config, GameConfig},
+    game::{Difficulty, Mode, Progression, Settings},
+    grid::Board,
+    point::FoodWeights,
+};
+
+/// The smallest board the snake can move on.
+const MIN_BOARD_SIZE: i32 = 5;
+
+/// Snake in the terminal
+#[derive(Parser)]
+#[command(version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub game: GameArgs,
+
+    /// The config file to use instead of the one in the config directory
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Play a game (the default)
+    Play(GameArgs),
+    /// Watch a recorded game
+    Replay {
+        /// The replay file, see the replays directory in the data directory
+        file: PathBuf,
+    },
+    /// Print the high score table
+    Scores,
+    /// Watch the computer play
+    Bot(GameArgs),
+    /// Play against someone else on the same keyboard, the turn keys
+    /// against the arrows
+    Versus {
+        #[command(flatten)]
+        game: GameArgs,
+
+        /// How many rounds to win to win the match
+        #[arg(long, default_value_t = ROUNDS_TO_WIN, value_parser = clap::value_parser!(u32).range(1..))]
+        rounds: u32,
+    },
+    /// Play the built-in levels one after the other
+    Campaign,
+    /// Draw a level, to play it with --level
+    Edit {
+        /// The level file to edit, created when saving if it doesn't exist
+        file: PathBuf,
+
+        /// Width of a new level in cells, defaults to the size of the terminal
+        #[arg(long)]
+        width: Option<u16>,
+
+        /// Height of a new level in cells, defaults to the size of the
+        /// terminal
+        #[arg(long)]
+        height: Option<u16>,
+    },
+    /// Host a match over the network, for players to join
+    #[cfg(feature = "net")]
+    Serve {
+        #[command(flatten)]
+        game: GameArgs,
+
+        /// The address to listen on
+        #[arg(long, default_value_t = format!("0.0.0.0:{DEFAULT_PORT}"))]
+        addr: String,
+
+        /// How many snakes play, the match starts once they all joined
+        #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..=MAX_PLAYERS as i64))]
+        players: u8,
+
+        /// How many rounds to win to win the match
+        #[arg(long, default_value_t = ROUNDS_TO_WIN, value_parser = clap::value_parser!(u32).range(1..))]
+        rounds: u32,
+
+        /// How many ticks the turns are delayed by, so that they apply on
+        /// the same tick for everyone, more for slower networks
+        #[arg(long, value_name = "TICKS", default_value_t = INPUT_DELAY)]
+        input_delay: u64,
+    },
+    /// Join a match hosted with serve
+    #[cfg(feature = "net")]
+    Join {
+        /// The address of the server, port 7777 if missing
+        addr: String,
+    },
+    /// Watch a match hosted with serve, without playing
+    #[cfg(feature = "net")]
+    Spectate {
+        /// The address of the server, port 7777 if missing
+        addr: String,
+    },
+    /// Host games over SSH, one for every session connecting
+    #[cfg(feature = "ssh")]
+    SshServe {
+        #[command(flatten)]
+        game: GameArgs,
+
+        /// The address to listen on
+        #[arg(long, default_value_t = format!("0.0.0.0:{}", ssh::DEFAULT_PORT))]
+        addr: String,
+
+        /// How many sessions can play at once, the next ones are turned
+        /// away
+        #[arg(long, default_value_t = ssh::MAX_SESSIONS as u16, value_parser = clap::value_parser!(u16).range(1..))]
+        max_sessions: u16,
+    },
+}
+
+#[derive(Args, Clone)]
+pub struct GameArgs {
+    /// Width of the board in cells, defaults to the size of the terminal
+    #[arg(long)]
+    pub width: Option<u16>,
+
+    /// Height of the board in cells, defaults to the size of the terminal
+    #[arg(long)]
+    pub height: Option<u16>,
+
+    /// The starting speed and how fast it increases: easy, normal, hard or
+    /// insane
+    #[arg(long)]
+    pub difficulty: Option<Difficulty>,
+
+    /// How many times per second the snake moves at the start, defaults to
+    /// the one of the difficulty
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=60))]
+    pub speed: Option<u32>,
+
+    /// Speed up every N foods eaten, 0 to keep the same speed
+    #[arg(long, value_name = "N")]
+    pub speed_up_every: Option<u32>,
+
+    /// How many cells long the snake is at the start
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    pub length: Option<u16>,
+
+    /// The rules to play with
+    #[arg(long)]
+    pub mode: Option<Mode>,
+
+    /// Play without the power-ups
+    #[arg(long)]
+    pub no_power_ups: bool,
+
+    /// The seed of the first game, to play the same game again
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// A level file to play on, its size replaces the width and the height
+    #[arg(long, value_name = "FILE")]
+    pub level: Option<PathBuf>,
+}
+
+impl GameArgs {
+    /// Fills the options missing from these arguments with `other`, e.g. the
+    /// options given before the subcommand.
+    pub fn or(self, other: GameArgs) -> GameArgs {
+        GameArgs {
+            width: self.width.or(other.width),
+            height: self.height.or(other.height),
+            difficulty: self.difficulty.or(other.difficulty),
+            speed: self.speed.or(other.speed),
+            speed_up_every: self.speed_up_every.or(other.speed_up_every),
+            length: self.length.or(other.length),
+            mode: self.mode.or(other.mode),
+            no_power_ups: self.no_power_ups || other.no_power_ups,
+            seed: self.seed.or(other.seed),
+            level: self.level.or(other.level),
+        }
+    }
+
+    /// The settings of the game, the options missing from the command line
+    /// being taken from `config`.
+    pub fn settings(&self, config: &Config) -> Settings {
+        let mode = self.mode.unwrap_or(config.game.mode);
+
+        Settings {
+            food: config.food.weights(mode),
+            ..self.game_settings(&config.game)
+        }
+    }
+
+    fn game_settings(&self, config: &GameConfig) -> Settings {
+        let difficulty = self.difficulty.unwrap_or(config.difficulty);
+        let progression = match self.speed_up_every.or(config.speed_up_every) {
+            Some(0) => None,
+            Some(foods_per_level) => Some(Progression {
+                foods_per_level,
+                ..difficulty.progression().unwrap_or_default()
+            }),
+            None => difficulty.progression(),
+        };
+
+        Settings {
+            speed: self.speed.or(config.speed).unwrap_or(difficulty.speed()),
+            starting_length: self.length.map_or(config.length, |length| length as usize),
+            mode: self.mode.unwrap_or(config.mode),
+            progression,
+            food: FoodWeights::default(),
+            power_ups: config.power_ups && !self.no_power_ups,
+        }
+    }
+
+    /// The board to play on, `terminal` being the size of the terminal in
+    /// cells. Fails when the snake doesn't fit in it.
+    pub fn board(&self, terminal: Board, config: &GameConfig) -> Result<Board, String> {
+        let width = self.width.or(config.width);
+        let height = self.height.or(config.height);
+        let board = Board::new(
+            width.map_or(terminal.width, i32::from),
+            height.map_or(terminal.height, i32::from),
+        );
+        let length = self.game_settings(config).starting_length as i32;
+
+        if board.width < MIN_BOARD_SIZE || board.height < MIN_BOARD_SIZE {
+            return Err(format!(
+                "the board must be at least {MIN_BOARD_SIZE}x{MIN_BOARD_SIZE}, got {}x{}",
+                board.width, board.height
+            ));
+        }
+        // the snake starts in the middle of the board and the outer cells
+        // are walls
+        if length > board.width - 3 {
+            return Err(format!(
+                "a snake of length {length} doesn't fit on a board {} cells wide",
+                board.width
+            ));
+        }
+
+        Ok(board)
+    }
+
+    /// The board of a match between `players` snakes, tall enough for each
+    /// of them to start on its own row.
+    pub fn arena_board(
+        &self,
+        terminal: Board,
+        config: &GameConfig,
+        players: usize,
+    ) -> Result<Board, String> {
+        let board = self.board(terminal, config)?;
+        let min_height = arena::min_height(players);
+
+        if board.height < min_height {
+            return Err(format!(
+                "{players} snakes need a board at least {min_height} cells high, got {}",
+                board.height
+            ));
+        }
+
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed_settings(args: &[&str], config: &Config) -> Settings {
+        let cli = Cli::try_parse_from([&["ratatui-snake"], args].concat()).unwrap();
+
+        cli.game.settings(config)
+    }
+
+    #[test]
+    fn the_command_line_overrides_the_config() {
+        let mut config =
+            Config::parse("[game]\nspeed = 12\nlength = 6\nmode = \"wrap\"\n").unwrap();
+        config
+            .apply_env([("RATATUI_SNAKE_SPEED".to_string(), "20".to_string())].into_iter())
+            .unwrap();
+
+        let settings = parsed_settings(&["--length", "9"], &config);
+        assert_eq!(settings.speed, 20);
+        assert_eq!(settings.starting_length, 9);
+        assert_eq!(settings.mode, Mode::Wrap);
+
+        let settings = parsed_settings(&["--speed", "30", "--mode", "classic"], &config);
+        assert_eq!(settings.speed, 30);
+        assert_eq!(settings.starting_length, 6);
+        assert_eq!(settings.mode, Mode::Classic);
+    }
+
+    #[test]
+    fn the_options_before_the_subcommand_fill_the_missing_ones() {
+        let cli = Cli::try_parse_from([
+            "ratatui-snake",
+            "--speed",
+            "15",
+            "--length",
+            "4",
+            "play",
+            "--length",
+            "8",
+        ])
+        .unwrap();
+        let Some(Command::Play(game)) = cli.command else {
+            panic!("not the play command");
+        };
+        let settings = game.or(cli.game).settings(&Config::default());
+
+        assert_eq!(settings.speed, 15);
+        assert_eq!(settings.starting_length, 8);
+    }
+}
@@ -0,0 +1,442 @@
+use std::{
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use ratatui::{style::Color, symbols::Marker};
+use serde::{de, Deserialize, Deserializer};
+use toml_edit::{value, Array, DocumentMut, Item, Table};
+
+use crate::{
+    game::{Difficulty, Mode, Settings},
+    keys::{Action, Key, KeyBindings},
+    point::FoodWeights,
+    ui::Theme,
+};
+
+/// Every environment variable read by [`Config::apply_env`] starts with it.
+const ENV_PREFIX: &str = "RATATUI_SNAKE_";
+
+/// The user configuration, read from a TOML file. Every key is optional and
+/// unknown keys are rejected, so typos don't go unnoticed:
+///
+/// ```toml
+/// [game]
+/// difficulty = "normal"
+/// speed = 12
+/// speed_up_every = 5
+/// length = 12
+/// mode = "classic"
+/// power_ups = true
+/// width = 80
+/// height = 48
+///
+/// [display]
+/// marker = "halfblock"
+///
+/// [colors]
+/// snake = "blue"
+/// food = "red"
+/// walls = "white"
+///
+/// [food.classic]
+/// nothing = 10
+/// bonus = 3
+/// poison = 0
+///
+/// [keys]
+/// up = ["w", "k", "up"]
+/// pause = ["p", "ctrl+z"]
+/// ```
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub game: GameConfig,
+    pub display: DisplayConfig,
+    pub colors: ColorsConfig,
+    pub food: FoodConfig,
+    /// The default bindings, with the keys of the actions listed in the
+    /// file replaced.
+    #[serde(deserialize_with = "key_bindings")]
+    pub keys: KeyBindings,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub difficulty: Difficulty,
+    /// The starting speed, the one of the difficulty if unset.
+    #[serde(deserialize_with = "speed")]
+    pub speed: Option<u32>,
+    /// How many foods make a level, overriding the progression of the
+    /// difficulty. 0 keeps the same speed for the whole game.
+    pub speed_up_every: Option<u32>,
+    #[serde(deserialize_with = "length")]
+    pub length: usize,
+    pub mode: Mode,
+    /// Whether power-ups appear on the board.
+    pub power_ups: bool,
+    /// The width of the board in cells, the terminal decides if unset.
+    pub width: Option<u16>,
+    /// The height of the board in cells, the terminal decides if unset.
+    pub height: Option<u16>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        let settings = Settings::default();
+
+        GameConfig {
+            difficulty: Difficulty::default(),
+            speed: None,
+            speed_up_every: None,
+            length: settings.starting_length,
+            mode: settings.mode,
+            power_ups: settings.power_ups,
+            width: None,
+            height: None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayConfig {
+    #[serde(deserialize_with = "from_str")]
+    pub marker: MarkerName,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        DisplayConfig {
+            marker: MarkerName(Theme::default().marker),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorsConfig {
+    #[serde(deserialize_with = "color")]
+    pub snake: Color,
+    #[serde(deserialize_with = "color")]
+    pub food: Color,
+    #[serde(deserialize_with = "color")]
+    pub walls: Color,
+}
+
+impl Default for ColorsConfig {
+    fn default() -> Self {
+        let theme = Theme::default();
+
+        ColorsConfig {
+            snake: theme.snake,
+            food: theme.food,
+            walls: theme.walls,
+        }
+    }
+}
+
+/// How often each special food appears, in every mode.
+#[derive(Deserialize, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct FoodConfig {
+    pub classic: FoodWeights,
+    pub wrap: FoodWeights,
+}
+
+impl FoodConfig {
+    pub fn weights(&self, mode: Mode) -> FoodWeights {
+        match mode {
+            Mode::Classic => self.classic,
+            Mode::Wrap => self.wrap,
+        }
+    }
+}
+
+/// A canvas marker that can be read from its name.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MarkerName(pub Marker);
+
+impl FromStr for MarkerName {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let marker = match name {
+            "braille" => Marker::Braille,
+            "halfblock" => Marker::HalfBlock,
+            "block" => Marker::Block,
+            "dot" => Marker::Dot,
+            "bar" => Marker::Bar,
+            _ => {
+                return Err(format!(
+                    "unknown marker '{name}', expected one of: braille, halfblock, block, dot, bar"
+                ))
+            }
+        };
+
+        Ok(MarkerName(marker))
+    }
+}
+
+impl Config {
+    /// Loads the configuration: the defaults, overridden by the config file
+    /// at `path` or [`config_path`], overridden by the environment. The
+    /// command line comes last and is up to the caller.
+    pub fn load(path: Option<&Path>) -> Result<Self, String> {
+        let mut config = match path.map(Path::to_path_buf).or_else(config_path) {
+            Some(path) => Config::load_file(&path)?,
+            None => Config::default(),
+        };
+
+        config.apply_env(env::vars())?;
+
+        Ok(config)
+    }
+
+    /// Reads the config file at `path`, the defaults if it doesn't exist.
+    pub fn load_file(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(content) => Config::parse(&content)
+                .map_err(|error| format!("invalid config file {}: {error}", path.display())),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(error) => Err(format!(
+                "cannot read config file {}: {error}",
+                path.display()
+            )),
+        }
+    }
+
+    /// Parses the content of a config file. The error tells the line and the
+    /// column of the faulty key or value.
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    /// Overrides the game and display settings with the `RATATUI_SNAKE_*`
+    /// variables among `vars`, e.g. `RATATUI_SNAKE_SPEED=20`.
+    pub fn apply_env(
+        &mut self,
+        vars: impl Iterator<Item = (String, String)>,
+    ) -> Result<(), String> {
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+
+            let invalid = |error: String| format!("invalid value '{value}' for {name}: {error}");
+
+            match key {
+                "DIFFICULTY" => self.game.difficulty = value.parse().map_err(invalid)?,
+                "SPEED" => self.game.speed = Some(parse_in_range(&value, 1, 60).map_err(invalid)?),
+                "SPEED_UP_EVERY" => {
+                    self.game.speed_up_every =
+                        Some(parse_in_range(&value, 0, u32::MAX).map_err(invalid)?)
+                }
+                "LENGTH" => {
+                    self.game.length =
+                        parse_in_range(&value, 1, u16::MAX as usize).map_err(invalid)?
+                }
+                "MODE" => self.game.mode = value.parse().map_err(invalid)?,
+                "POWER_UPS" => {
+                    self.game.power_ups = value
+                        .parse()
+                        .map_err(|_| invalid("expected true or false".to_string()))?
+                }
+                "WIDTH" => {
+                    self.game.width = Some(parse_in_range(&value, 1, u16::MAX).map_err(invalid)?)
+                }
+                "HEIGHT" => {
+                    self.game.height = Some(parse_in_range(&value, 1, u16::MAX).map_err(invalid)?)
+                }
+                "MARKER" => self.display.marker = value.parse().map_err(invalid)?,
+                "CONFIG" => {}
+                _ => return Err(format!("unknown environment variable {name}")),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn theme(&self) -> Theme {
+        Theme {
+            snake: self.colors.snake,
+            food: self.colors.food,
+            walls: self.colors.walls,
+            marker: self.display.marker.0,
+        }
+    }
+}
+
+/// Where the config file is: `$RATATUI_SNAKE_CONFIG` if set, otherwise
+/// `config.toml` in the XDG config directory.
+pub fn config_path() -> Option<PathBuf> {
+    env::var_os(format!("{ENV_PREFIX}CONFIG"))
+        .map(PathBuf::from)
+        .or_else(|| dirs::config_dir().map(|dir| dir.join("ratatui-snake").join("config.toml")))
+}
+
+/// Writes `bindings` to the `[keys]` table of the config file at `path`,
+/// leaving the rest of the file, comments included, untouched.
+pub fn save_key_bindings(path: &Path, bindings: &KeyBindings) -> Result<(), String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(error) => {
+            return Err(format!(
+                "cannot read config file {}: {error}",
+                path.display()
+            ))
+        }
+    };
+    let mut document: DocumentMut = content
+        .parse()
+        .map_err(|error| format!("invalid config file {}: {error}", path.display()))?;
+
+    let mut keys = Table::new();
+    for action in Action::ALL {
+        let names: Array = bindings.keys(action).iter().map(Key::to_string).collect();
+        keys[action.name()] = value(names);
+    }
+    document["keys"] = Item::Table(keys);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|error| format!("cannot create {}: {error}", dir.display()))?;
+    }
+    fs::write(path, document.to_string())
+        .map_err(|error| format!("cannot write config file {}: {error}", path.display()))
+}
+
+fn parse_in_range<T>(value: &str, min: T, max: T) -> Result<T, String>
+where
+    T: FromStr + PartialOrd + std::fmt::Display,
+{
+    match value.parse() {
+        Ok(number) if number >= min && number <= max => Ok(number),
+        _ => Err(format!("expected a number between {min} and {max}")),
+    }
+}
+
+fn from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err = String>,
+{
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(de::Error::custom)
+}
+
+fn speed<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    let speed = u32::deserialize(deserializer)?;
+
+    parse_in_range(&speed.to_string(), 1, 60)
+        .map(Some)
+        .map_err(de::Error::custom)
+}
+
+fn length<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    let length = usize::deserialize(deserializer)?;
+
+    parse_in_range(&length.to_string(), 1, u16::MAX as usize).map_err(de::Error::custom)
+}
+
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let name = String::deserialize(deserializer)?;
+
+    name.parse()
+        .map_err(|_| de::Error::custom(format!("unknown color '{name}'")))
+}
+
+fn key_bindings<'de, D: Deserializer<'de>>(deserializer: D) -> Result<KeyBindings, D::Error> {
+    let overrides = HashMap::<Action, Vec<Key>>::deserialize(deserializer)?;
+
+    KeyBindings::default()
+        .with_overrides(overrides)
+        .map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> impl Iterator<Item = (String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn the_environment_overrides_the_file_over_the_defaults() {
+        let path =
+            env::temp_dir().join(format!("ratatui-snake-config-{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            "[game]\nspeed = 12\nlength = 6\n\n[display]\nmarker = \"dot\"\n",
+        )
+        .unwrap();
+        let mut config = Config::load_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.game.speed, Some(12));
+        assert_eq!(config.game.length, 6);
+        assert_eq!(config.display.marker, MarkerName(Marker::Dot));
+        assert_eq!(config.game.mode, Mode::default());
+        assert_eq!(config.keys, KeyBindings::default());
+
+        config
+            .apply_env(vars(&[
+                ("RATATUI_SNAKE_SPEED", "20"),
+                ("RATATUI_SNAKE_MODE", "wrap"),
+                ("SPEED", "30"),
+            ]))
+            .unwrap();
+
+        assert_eq!(config.game.speed, Some(20));
+        assert_eq!(config.game.mode, Mode::Wrap);
+        assert_eq!(config.game.length, 6);
+    }
+
+    #[test]
+    fn a_missing_file_is_the_defaults() {
+        let path = env::temp_dir().join("ratatui-snake-no-such-config.toml");
+        let config = Config::load_file(&path).unwrap();
+
+        assert_eq!(config.game.speed, None);
+        assert_eq!(config.game.length, Settings::default().starting_length);
+    }
+
+    #[test]
+    fn tells_the_line_of_the_error() {
+        let invalid = [
+            ("[game]\nspeed = 12\n\nlength = 0\n", "line 4"),
+            ("[game]\nspeed = 12\n\nsped = 10\n", "line 4"),
+            ("[colors]\nsnake = \"blue\"\n\nfood = \"redd\"\n", "line 4"),
+            // a conflict is between several keys, the table is blamed
+            ("\n[keys]\nup = [\"w\"]\ndown = [\"w\"]\n", "line 2"),
+        ];
+
+        for (content, line) in invalid {
+            let error = Config::parse(content).err().unwrap().to_string();
+            assert!(error.contains(line), "{error}");
+        }
+    }
+
+    #[test]
+    fn rejects_bad_variables() {
+        let mut config = Config::default();
+
+        let error = config
+            .apply_env(vars(&[("RATATUI_SNAKE_SPEED", "100")]))
+            .unwrap_err();
+        assert!(error.contains("RATATUI_SNAKE_SPEED"), "{error}");
+        assert!(config
+            .apply_env(vars(&[("RATATUI_SNAKE_SPED", "10")]))
+            .is_err());
+        assert_eq!(config.game.speed, None);
+    }
+}
@@ -0,0 +1,261 @@
+use std::{collections::HashSet, fmt};
+
+use crate::{
+    game::Direction,
+    grid::{Board, Cell},
+    level::Level,
+};
+
+/// What painting a cell puts on it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Brush {
+    Wall,
+    /// A cell the food spawns on.
+    Food,
+    Erase,
+}
+
+impl fmt::Display for Brush {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Brush::Wall => "wall",
+            Brush::Food => "food",
+            Brush::Erase => "eraser",
+        })
+    }
+}
+
+/// A [`Level`] being edited, with the cursor the player paints with. It
+/// does not know anything about the terminal, like the [`crate::engine`].
+pub struct Editor {
+    pub name: String,
+    pub board: Board,
+    walls: HashSet<Cell>,
+    food: HashSet<Cell>,
+    pub spawn: Cell,
+    pub direction: Direction,
+    pub cursor: Cell,
+    pub brush: Brush,
+    /// Whether moving the cursor paints the cells it goes through.
+    pub drawing: bool,
+    /// Whether there are changes not saved yet.
+    pub modified: bool,
+    /// The outcome of the last action, e.g. where the level was saved.
+    pub message: Option<String>,
+}
+
+impl Editor {
+    /// An empty level surrounded by walls.
+    pub fn new(name: String, board: Board) -> Self {
+        let walls = (0..board.width)
+            .flat_map(|x| (0..board.height).map(move |y| Cell::new(x, y)))
+            .filter(|&cell| board.is_edge(cell))
+            .collect();
+
+        Editor {
+            name,
+            board,
+            walls,
+            food: HashSet::new(),
+            spawn: board.center(),
+            direction: Direction::Right,
+            cursor: board.center(),
+            brush: Brush::Wall,
+            drawing: false,
+            modified: true,
+            message: None,
+        }
+    }
+
+    pub fn from_level(level: &Level) -> Self {
+        Editor {
+            name: level.name.clone(),
+            board: level.board,
+            walls: level.walls.iter().copied().collect(),
+            food: level.food.iter().copied().collect(),
+            spawn: level.spawn,
+            direction: level.direction,
+            cursor: level.spawn,
+            brush: Brush::Wall,
+            drawing: false,
+            modified: false,
+            message: None,
+        }
+    }
+
+    /// The level as edited so far.
+    pub fn level(&self) -> Level {
+        Level {
+            name: self.name.clone(),
+            board: self.board,
+            walls: self.walls.iter().copied().collect(),
+            spawn: self.spawn,
+            direction: self.direction,
+            food: self.food.iter().copied().collect(),
+            goal: None,
+        }
+    }
+
+    pub fn walls(&self) -> impl Iterator<Item = &Cell> {
+        self.walls.iter()
+    }
+
+    pub fn food(&self) -> impl Iterator<Item = &Cell> {
+        self.food.iter()
+    }
+
+    /// Moves the cursor one cell, painting the new cell when drawing. The
+    /// cursor stays on the board.
+    pub fn move_cursor(&mut self, direction: Direction) {
+        let next = self.cursor.neighbour(direction);
+
+        if self.board.contains(next) {
+            self.cursor = next;
+
+            if self.drawing {
+                self.paint(next, self.brush);
+            }
+        }
+    }
+
+    /// Paints `cell` with `brush`. The start of the snake can't be covered
+    /// by a wall.
+    pub fn paint(&mut self, cell: Cell, brush: Brush) {
+        if !self.board.contains(cell) {
+            return;
+        }
+
+        let changed = match brush {
+            Brush::Wall if cell == self.spawn => false,
+            Brush::Wall => self.food.remove(&cell) | self.walls.insert(cell),
+            Brush::Food => self.walls.remove(&cell) | self.food.insert(cell),
+            Brush::Erase => self.walls.remove(&cell) | self.food.remove(&cell),
+        };
+
+        self.modified |= changed;
+    }
+
+    /// Moves the start of the snake to the cursor, clearing the wall there.
+    pub fn place_spawn(&mut self) {
+        self.walls.remove(&self.cursor);
+        self.spawn = self.cursor;
+        self.modified = true;
+    }
+
+    /// Turns the start of the snake clockwise.
+    pub fn rotate_spawn(&mut self) {
+        self.direction = match self.direction {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        };
+        self.modified = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    fn editor() -> Editor {
+        let mut editor = Editor::new("test".to_string(), Board::new(12, 8));
+        editor.modified = false;
+
+        editor
+    }
+
+    fn cells<'a>(cells: impl Iterator<Item = &'a Cell>) -> HashSet<Cell> {
+        cells.copied().collect()
+    }
+
+    #[test]
+    fn paints_and_erases_walls_and_food() {
+        let mut editor = editor();
+        let cell = Cell::new(3, 3);
+
+        editor.paint(cell, Brush::Wall);
+        assert!(editor.walls.contains(&cell));
+        assert!(editor.modified);
+
+        // a cell holds a wall or food, not both
+        editor.paint(cell, Brush::Food);
+        assert!(editor.food.contains(&cell));
+        assert!(!editor.walls.contains(&cell));
+
+        editor.paint(cell, Brush::Erase);
+        assert!(!editor.food.contains(&cell));
+        assert!(!editor.walls.contains(&cell));
+
+        editor.modified = false;
+        editor.paint(cell, Brush::Erase);
+        editor.paint(Cell::new(20, 3), Brush::Wall);
+        assert!(!editor.modified);
+    }
+
+    #[test]
+    fn draws_along_the_cursor() {
+        let mut editor = editor();
+        editor.cursor = Cell::new(2, 2);
+        editor.drawing = true;
+
+        for _ in 0..3 {
+            editor.move_cursor(Direction::Right);
+        }
+        assert_eq!(editor.cursor, Cell::new(5, 2));
+        assert!((3..=5).all(|x| editor.walls.contains(&Cell::new(x, 2))));
+
+        // the cursor stops on the edge
+        for _ in 0..20 {
+            editor.move_cursor(Direction::Down);
+        }
+        assert_eq!(editor.cursor, Cell::new(5, 0));
+    }
+
+    #[test]
+    fn keeps_the_spawn_clear() {
+        let mut editor = editor();
+        editor.cursor = Cell::new(3, 3);
+        editor.paint(editor.cursor, Brush::Wall);
+
+        editor.place_spawn();
+        assert_eq!(editor.spawn, Cell::new(3, 3));
+        assert!(!editor.walls.contains(&editor.spawn));
+
+        editor.paint(editor.spawn, Brush::Wall);
+        assert!(!editor.walls.contains(&editor.spawn));
+
+        editor.rotate_spawn();
+        assert_eq!(editor.direction, Direction::Down);
+    }
+
+    #[test]
+    fn a_saved_level_loads_the_same() {
+        let mut editor = editor();
+        editor.paint(Cell::new(4, 4), Brush::Wall);
+        editor.paint(Cell::new(6, 2), Brush::Food);
+        editor.paint(Cell::new(7, 2), Brush::Food);
+        editor.cursor = Cell::new(2, 5);
+        editor.place_spawn();
+        editor.rotate_spawn();
+
+        let path = env::temp_dir().join(format!("ratatui-snake-level-{}.txt", std::process::id()));
+        editor.level().save(&path).unwrap();
+        let level = Level::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let loaded = Editor::from_level(&level);
+
+        assert_eq!(loaded.board, editor.board);
+        assert_eq!(cells(loaded.walls()), cells(editor.walls()));
+        assert_eq!(cells(loaded.food()), cells(editor.food()));
+        assert_eq!(loaded.spawn, Cell::new(2, 5));
+        assert_eq!(loaded.direction, Direction::Down);
+        assert!(!loaded.modified);
+        assert_eq!(
+            Level::parse(level.name.clone(), &editor.level().to_text()).unwrap(),
+            level
+        );
+    }
+}
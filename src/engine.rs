@@ -0,0 +1,943 @@
+use std::hash::{Hash, Hasher};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{Direction, Game, GameState, Mode, Settings, TurnQueue},
+    grid::{Board, Cell},
+    level::{Goal, Level},
+    point::{FoodKind, Point, BONUS_SCORE, SHRINK_CELLS, SLOW_DOWN, SPECIAL_LIFETIME_MS},
+    power_up::{Active, Pickup, PowerUp, MAGNET_RANGE, POWER_UP_CHANCE},
+    snake::Snake,
+    walls::Walls,
+};
+
+/// What happened during a single call to [`Engine::tick`].
+#[derive(Default)]
+pub struct Events {
+    /// Whether the simulation moved at all, it doesn't unless running.
+    pub ticked: bool,
+    /// The turn taken by the snake on this tick.
+    pub turn: Option<Direction>,
+    pub ate_food: bool,
+    /// The special food eaten on this tick, if any.
+    pub ate_special: Option<FoodKind>,
+    /// Whether the snake moves slower from now on.
+    pub slowed: bool,
+    /// Whether the snake reached a new level, and moves faster from now on.
+    pub level_up: bool,
+    /// The power-up picked up on this tick, if any.
+    pub power_up: Option<PowerUp>,
+    /// Whether a power-up started or ran out, which can change
+    /// [`Engine::ticks_per_second`].
+    pub power_ups_changed: bool,
+    pub game_over: bool,
+    pub won: bool,
+}
+
+/// Owns the whole game simulation. It does not know anything about the
+/// terminal: the caller decides when to tick it and renders from its state.
+///
+/// All the randomness comes from a RNG seeded with `seed`, so two engines
+/// created with the same seed and fed the same turns play the same game.
+#[derive(Serialize, Deserialize)]
+pub struct Engine {
+    pub board: Board,
+    pub settings: Settings,
+    /// The seed of the current game.
+    pub seed: u64,
+    /// How many ticks the current game has been running for.
+    pub ticks: u64,
+    /// How long the current game has been running for in game time, in
+    /// milliseconds. Unlike `ticks` it accounts for the speed changes.
+    #[serde(default)]
+    pub elapsed_ms: u64,
+    pub game: Game,
+    pub snake: Snake,
+    /// The food to eat, `None` once the snake filled the board.
+    pub point: Option<Point>,
+    /// A special food that appears from time to time after eating, see
+    /// [`crate::point::FoodWeights`].
+    #[serde(default)]
+    pub special: Option<Point>,
+    /// A power-up that appears from time to time after eating, when the
+    /// settings allow them.
+    #[serde(default)]
+    pub power_up: Option<Pickup>,
+    /// The power-ups picked up and still running.
+    #[serde(default)]
+    pub active: Vec<Active>,
+    pub walls: Walls,
+    /// The level the game is played on, the board surrounded by walls if
+    /// `None`.
+    #[serde(default)]
+    pub level: Option<Level>,
+    /// Turns waiting to be applied, one per tick.
+    turns: TurnQueue,
+    rng: ChaCha8Rng,
+}
+
+impl Engine {
+    /// Creates an engine for a game on `board`, which wraps around in the
+    /// [`Mode::Wrap`] mode.
+    pub fn new(board: Board, settings: Settings, seed: u64) -> Self {
+        let board = board.with_wrap(settings.mode == Mode::Wrap);
+
+        Engine::build(board, Walls::new(board), None, settings, seed)
+    }
+
+    /// Creates an engine for a game on `level`, with its walls, where the
+    /// snake and the food spawn.
+    pub fn with_level(level: Level, settings: Settings, seed: u64) -> Self {
+        let board = level.board.with_wrap(settings.mode == Mode::Wrap);
+        let walls = Walls::from_cells(board, level.walls.iter().copied());
+
+        Engine::build(board, walls, Some(level), settings, seed)
+    }
+
+    fn build(
+        board: Board,
+        walls: Walls,
+        level: Option<Level>,
+        settings: Settings,
+        seed: u64,
+    ) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let snake = Engine::new_snake(board, &settings, level.as_ref());
+        let point = Point::spawn(board, &walls, &snake, food_zone(&level), &[], &mut rng);
+
+        Engine {
+            board,
+            seed,
+            ticks: 0,
+            elapsed_ms: 0,
+            game: Game::new(settings.speed),
+            settings,
+            point,
+            special: None,
+            power_up: None,
+            active: Vec::new(),
+            snake,
+            walls,
+            level,
+            turns: TurnQueue::default(),
+            rng,
+        }
+    }
+
+    /// Leaves the startup screen, [`Engine::tick`] does nothing before.
+    pub fn start(&mut self) {
+        if self.game.state == GameState::Startup {
+            self.game.state = GameState::Running;
+        }
+    }
+
+    /// Queues a turn to be applied on one of the next ticks, see
+    /// [`TurnQueue::push`].
+    pub fn queue_turn(&mut self, direction: Direction) {
+        if self.game.state != GameState::Running {
+            return;
+        }
+
+        self.turns.push(direction, self.snake.direction);
+    }
+
+    /// Advances the simulation by one step, applying the next queued turn.
+    pub fn tick(&mut self) -> Events {
+        let mut events = Events::default();
+
+        if self.game.state != GameState::Running {
+            return events;
+        }
+
+        events.ticked = true;
+        self.ticks += 1;
+        self.elapsed_ms += 1000 / self.ticks_per_second().max(1) as u64;
+        self.expire_power_ups(&mut events);
+
+        if let Some(direction) = self.turns.pop() {
+            self.snake.change_direction(direction);
+            events.turn = Some(direction);
+        }
+        self.snake.move_snake(&self.board);
+
+        let head = self.snake.head();
+
+        if (self.snake.bites_itself() && !self.has_power_up(PowerUp::Ghost))
+            || self.walls.contains(head)
+        {
+            self.game.game_over();
+            events.game_over = true;
+
+            return events;
+        }
+
+        if self.special.as_ref().is_some_and(|special| {
+            special
+                .expires_ms
+                .is_some_and(|expires| expires <= self.elapsed_ms)
+        }) {
+            self.special = None;
+        }
+
+        if let Some(pickup) = self.power_up.take_if(|pickup| pickup.cell == head) {
+            self.active
+                .retain(|active| active.power_up != pickup.power_up);
+            self.active.push(Active {
+                power_up: pickup.power_up,
+                ends_ms: self.elapsed_ms + pickup.power_up.duration_ms(),
+            });
+            events.power_up = Some(pickup.power_up);
+            events.power_ups_changed = true;
+        }
+
+        if let Some(special) = self.special.take_if(|special| special.cell == head) {
+            events.ate_special = Some(special.kind);
+
+            match special.kind {
+                FoodKind::Normal => {
+                    self.snake.grow();
+                    self.game.eat(1);
+                }
+                FoodKind::Bonus => {
+                    self.snake.grow();
+                    self.game.eat(BONUS_SCORE);
+                }
+                FoodKind::Shrink => {
+                    self.snake.shrink(SHRINK_CELLS);
+                    self.game.eat(1);
+                }
+                FoodKind::Slow => {
+                    self.game.slow_down(SLOW_DOWN);
+                    self.game.eat(1);
+                    events.slowed = true;
+                }
+                FoodKind::Poison => {
+                    self.game.game_over();
+                    events.game_over = true;
+
+                    return events;
+                }
+            }
+
+            self.level_up(&mut events);
+        }
+
+        if self.point.as_ref().is_some_and(|point| point.cell == head) {
+            self.snake.grow();
+            self.game.eat(1);
+            events.ate_food = true;
+
+            self.level_up(&mut events);
+
+            if self.fills_board() {
+                self.point = None;
+                self.game.win();
+                events.won = true;
+            } else {
+                let taken = self.taken_cells();
+                // the last free cells may hold the special food or the
+                // power-up, the food goes on one of them then
+                self.point = Point::spawn(
+                    self.board,
+                    &self.walls,
+                    &self.snake,
+                    food_zone(&self.level),
+                    &taken,
+                    &mut self.rng,
+                )
+                .or_else(|| {
+                    Point::spawn(
+                        self.board,
+                        &self.walls,
+                        &self.snake,
+                        food_zone(&self.level),
+                        &[],
+                        &mut self.rng,
+                    )
+                });
+
+                if self.special.is_none() {
+                    self.spawn_special();
+                }
+                if self.power_up.is_none() {
+                    self.spawn_power_up();
+                }
+            }
+        }
+
+        if self.has_power_up(PowerUp::Magnet) {
+            self.attract_food();
+        }
+
+        if !events.won && self.goal().is_some_and(|goal| goal.is_reached(self)) {
+            self.game.win();
+            events.won = true;
+        }
+
+        events
+    }
+
+    /// Goes up a level once enough foods were eaten for the next one.
+    fn level_up(&mut self, events: &mut Events) {
+        let Some(progression) = self.settings.progression else {
+            return;
+        };
+        let level = progression.level(self.game.foods);
+
+        if level > self.game.level() {
+            let speed = progression.speed(self.settings.speed, level);
+            self.game.level_up(level, speed);
+            events.level_up = true;
+        }
+    }
+
+    /// Maybe places a special food, depending on the weights of the
+    /// settings. It spawns anywhere, not only in the food zone of the level.
+    fn spawn_special(&mut self) {
+        let Some(kind) = self.settings.food.pick(&mut self.rng) else {
+            return;
+        };
+        let taken = self.taken_cells();
+
+        self.special = Point::spawn(
+            self.board,
+            &self.walls,
+            &self.snake,
+            &[],
+            &taken,
+            &mut self.rng,
+        )
+        .map(|point| Point {
+            kind,
+            expires_ms: Some(self.elapsed_ms + SPECIAL_LIFETIME_MS),
+            ..point
+        });
+    }
+
+    /// Maybe places a power-up, when the settings allow them. Like the
+    /// special food it spawns anywhere.
+    fn spawn_power_up(&mut self) {
+        if !self.settings.power_ups || !self.rng.gen_ratio(1, POWER_UP_CHANCE) {
+            return;
+        }
+        let power_up = PowerUp::ALL[self.rng.gen_range(0..PowerUp::ALL.len())];
+        let taken = self.taken_cells();
+
+        self.power_up = Point::spawn(
+            self.board,
+            &self.walls,
+            &self.snake,
+            &[],
+            &taken,
+            &mut self.rng,
+        )
+        .map(|point| Pickup {
+            cell: point.cell,
+            power_up,
+            expires_ms: self.elapsed_ms + SPECIAL_LIFETIME_MS,
+        });
+    }
+
+    /// Removes the power-ups which ran out and the one on the board if it
+    /// has been waiting too long.
+    fn expire_power_ups(&mut self, events: &mut Events) {
+        let now = self.elapsed_ms;
+        let count = self.active.len();
+
+        self.active.retain(|active| active.ends_ms > now);
+        events.power_ups_changed |= self.active.len() != count;
+
+        if self
+            .power_up
+            .as_ref()
+            .is_some_and(|pickup| pickup.expires_ms <= now)
+        {
+            self.power_up = None;
+        }
+    }
+
+    /// Whether `power_up` is running.
+    pub fn has_power_up(&self, power_up: PowerUp) -> bool {
+        self.active.iter().any(|active| active.power_up == power_up)
+    }
+
+    /// How fast the game runs right now: the speed of the game, halved in
+    /// slow motion.
+    pub fn ticks_per_second(&self) -> u32 {
+        let speed = self.game.speed();
+
+        if self.has_power_up(PowerUp::SlowMotion) {
+            (speed / 2).max(1)
+        } else {
+            speed
+        }
+    }
+
+    /// Moves the food within [`MAGNET_RANGE`] of the head one cell closer
+    /// to it, the poison excepted. Food doesn't go through walls, the snake
+    /// or other items.
+    fn attract_food(&mut self) {
+        let head = self.snake.head();
+        let mut taken = self.taken_cells();
+
+        for point in self.point.iter_mut().chain(self.special.iter_mut()) {
+            if point.kind == FoodKind::Poison {
+                continue;
+            }
+            let (dx, dy) = (head.x - point.cell.x, head.y - point.cell.y);
+
+            if dx.abs() + dy.abs() > MAGNET_RANGE {
+                continue;
+            }
+            let direction = if dx.abs() >= dy.abs() {
+                if dx > 0 {
+                    Direction::Right
+                } else {
+                    Direction::Left
+                }
+            } else if dy > 0 {
+                Direction::Up
+            } else {
+                Direction::Down
+            };
+            let next = point.cell.neighbour(direction);
+
+            if !self.walls.contains(next) && !self.snake.contains(next) && !taken.contains(&next) {
+                taken.retain(|&cell| cell != point.cell);
+                taken.push(next);
+                point.cell = next;
+            }
+        }
+    }
+
+    /// The cells taken by the food and the power-up.
+    fn taken_cells(&self) -> Vec<Cell> {
+        self.point
+            .iter()
+            .chain(&self.special)
+            .map(|point| point.cell)
+            .chain(self.power_up.iter().map(|pickup| pickup.cell))
+            .collect()
+    }
+
+    /// Whether the snake covers every cell it can reach, which wins the
+    /// game.
+    fn fills_board(&self) -> bool {
+        self.board
+            .flood_fill(self.snake.head(), |cell| self.walls.contains(cell))
+            .into_iter()
+            .all(|cell| self.snake.contains(cell))
+    }
+
+    /// What clears the level being played, if anything but filling the
+    /// board does.
+    pub fn goal(&self) -> Option<Goal> {
+        self.level.as_ref().and_then(|level| level.goal)
+    }
+
+    /// Starts a new game. Its seed is drawn from the current RNG, so a
+    /// whole session started from the same seed is reproducible too.
+    pub fn restart(&mut self) {
+        self.seed = self.rng.gen();
+        self.rng = ChaCha8Rng::seed_from_u64(self.seed);
+        self.ticks = 0;
+        self.elapsed_ms = 0;
+        self.game.restart(self.settings.speed);
+        self.snake = Engine::new_snake(self.board, &self.settings, self.level.as_ref());
+        self.point = Point::spawn(
+            self.board,
+            &self.walls,
+            &self.snake,
+            food_zone(&self.level),
+            &[],
+            &mut self.rng,
+        );
+        self.special = None;
+        self.power_up = None;
+        self.active.clear();
+        self.turns.clear();
+    }
+
+    /// A hash of everything the simulation depends on, used to check that a
+    /// replay plays out exactly like the recorded game.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = Fnv1a::default();
+
+        self.ticks.hash(&mut hasher);
+        self.game.score.hash(&mut hasher);
+        self.game.state.hash(&mut hasher);
+        self.snake.direction.hash(&mut hasher);
+        self.snake.cells().for_each(|cell| cell.hash(&mut hasher));
+        self.point
+            .as_ref()
+            .map(|point| point.cell)
+            .hash(&mut hasher);
+        // only hashed when there is one, so the games recorded before
+        // special foods existed still replay
+        if let Some(special) = &self.special {
+            special.cell.hash(&mut hasher);
+            special.kind.hash(&mut hasher);
+        }
+        if let Some(pickup) = &self.power_up {
+            pickup.cell.hash(&mut hasher);
+            pickup.power_up.hash(&mut hasher);
+        }
+        self.active
+            .iter()
+            .for_each(|active| active.power_up.hash(&mut hasher));
+
+        hasher.finish()
+    }
+
+    fn new_snake(board: Board, settings: &Settings, level: Option<&Level>) -> Snake {
+        let length = settings.starting_length;
+
+        if let Some(level) = level {
+            return Snake::new(level.spawn, level.direction, length);
+        }
+
+        let center = board.center();
+        let head = Cell::new(center.x + length as i32 / 2, center.y);
+
+        Snake::new(head, Direction::Right, length)
+    }
+}
+
+/// The cells the food spawns on, anywhere if empty.
+fn food_zone(level: &Option<Level>) -> &[Cell] {
+    level.as_ref().map_or(&[], |level| &level.food)
+}
+
+/// The FNV-1a hash function: unlike the std `DefaultHasher`, its output is
+/// guaranteed not to change between Rust releases, which replays rely on.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bot, game::Progression};
+
+    fn running_engine() -> Engine {
+        let mut engine = Engine::new(Board::new(40, 20), Settings::default(), 7);
+        engine.start();
+
+        engine
+    }
+
+    #[test]
+    fn moves_the_snake_one_cell_per_tick() {
+        let mut engine = running_engine();
+        engine.snake = Snake::new(Cell::new(10, 10), Direction::Right, 3);
+        engine.point = Some(Point::new(Cell::new(30, 15)));
+
+        let events = engine.tick();
+
+        assert!(events.ticked);
+        assert!(!events.ate_food);
+        assert_eq!(engine.ticks, 1);
+        assert_eq!(
+            engine.snake.cells().copied().collect::<Vec<_>>(),
+            [Cell::new(11, 10), Cell::new(10, 10), Cell::new(9, 10)]
+        );
+    }
+
+    #[test]
+    fn grows_and_scores_when_eating() {
+        let mut engine = running_engine();
+        engine.snake = Snake::new(Cell::new(10, 10), Direction::Right, 3);
+        engine.point = Some(Point::new(Cell::new(11, 10)));
+        engine.special = None;
+
+        let events = engine.tick();
+
+        assert!(events.ate_food);
+        assert_eq!(engine.game.score, 1);
+        assert_eq!(engine.game.foods, 1);
+        assert_ne!(
+            engine.point.as_ref().map(|point| point.cell),
+            Some(Cell::new(11, 10))
+        );
+
+        engine.tick();
+        assert_eq!(engine.snake.len(), 4);
+        assert_eq!(engine.snake.head(), Cell::new(12, 10));
+    }
+
+    #[test]
+    fn never_spawns_the_food_on_the_snake_or_the_walls() {
+        let level =
+            Level::parse("pillars".to_string(), include_str!("../levels/pillars.txt")).unwrap();
+
+        for seed in 0..5 {
+            let mut engine = Engine::with_level(level.clone(), Settings::default(), seed);
+            engine.start();
+
+            while engine.game.state == GameState::Running && engine.ticks < 200 {
+                if let Some(direction) = bot::next_turn(&engine) {
+                    engine.queue_turn(direction);
+                }
+                engine.tick();
+
+                for point in engine.point.iter().chain(&engine.special) {
+                    assert!(!engine.snake.contains(point.cell));
+                    assert!(!engine.walls.contains(point.cell));
+                }
+            }
+        }
+    }
+
+    /// A running engine whose snake of 6 cells is about to eat a special
+    /// food of `kind`, the food far away.
+    fn engine_eating(kind: FoodKind, settings: Settings) -> Engine {
+        let mut engine = Engine::new(Board::new(40, 20), settings, 7);
+        engine.start();
+        engine.snake = Snake::new(Cell::new(10, 10), Direction::Right, 6);
+        engine.point = Some(Point::new(Cell::new(30, 15)));
+        engine.special = Some(Point {
+            kind,
+            ..Point::new(Cell::new(11, 10))
+        });
+        engine.power_up = None;
+
+        engine
+    }
+
+    #[test]
+    fn the_bonus_scores_more() {
+        let mut engine = engine_eating(FoodKind::Bonus, Settings::default());
+
+        let events = engine.tick();
+
+        assert_eq!(events.ate_special, Some(FoodKind::Bonus));
+        assert_eq!(engine.game.score, BONUS_SCORE);
+        assert_eq!(engine.game.foods, 1);
+        assert!(engine.special.is_none());
+
+        engine.tick();
+        assert_eq!(engine.snake.len(), 7);
+    }
+
+    #[test]
+    fn the_shrink_food_shortens_the_snake() {
+        let mut engine = engine_eating(FoodKind::Shrink, Settings::default());
+
+        engine.tick();
+
+        assert_eq!(engine.snake.len(), 6 - SHRINK_CELLS);
+        assert_eq!(engine.snake.head(), Cell::new(11, 10));
+        assert_eq!(engine.game.score, 1);
+    }
+
+    #[test]
+    fn the_slow_food_slows_down_for_the_rest_of_the_game() {
+        let settings = Settings {
+            speed: 10,
+            progression: Some(Progression {
+                foods_per_level: 2,
+                speed_step: 1,
+                max_speed: 20,
+            }),
+            ..Settings::default()
+        };
+        let mut engine = engine_eating(FoodKind::Slow, settings);
+
+        let events = engine.tick();
+
+        assert!(events.slowed);
+        assert_eq!(engine.ticks_per_second(), 10 - SLOW_DOWN);
+
+        // the second food levels up, still slowed down
+        engine.point = Some(Point::new(Cell::new(12, 10)));
+        let events = engine.tick();
+
+        assert!(events.level_up);
+        assert_eq!(engine.ticks_per_second(), 11 - SLOW_DOWN);
+    }
+
+    #[test]
+    fn the_poison_ends_the_game() {
+        let mut engine = engine_eating(FoodKind::Poison, Settings::default());
+
+        let events = engine.tick();
+
+        assert!(events.game_over);
+        assert!(engine.game.state == GameState::GameOver);
+        assert_eq!(engine.game.score, 0);
+    }
+
+    /// A running engine with `power_up` running, the snake 8 cells long
+    /// going right from (10, 10) and nothing else on the board.
+    fn engine_with(power_up: PowerUp) -> Engine {
+        let mut engine = running_engine();
+        engine.snake = Snake::new(Cell::new(10, 10), Direction::Right, 8);
+        engine.point = Some(Point::new(Cell::new(30, 15)));
+        engine.special = None;
+        engine.power_up = None;
+        engine.active = vec![Active {
+            power_up,
+            ends_ms: u64::MAX,
+        }];
+
+        engine
+    }
+
+    #[test]
+    fn a_ghost_goes_through_its_body() {
+        let mut engine = engine_with(PowerUp::Ghost);
+        let crossing = Cell::new(9, 10);
+
+        for direction in [Direction::Up, Direction::Left, Direction::Down] {
+            engine.queue_turn(direction);
+            engine.tick();
+        }
+        assert_eq!(engine.snake.head(), crossing);
+        assert!(engine.snake.bites_itself());
+        assert!(engine.game.state == GameState::Running);
+
+        engine.tick();
+        assert!(!engine.snake.bites_itself());
+        assert_eq!(engine.snake.len(), 8);
+
+        // the crossing is covered twice, it stays in the body until the
+        // second time leaves
+        for _ in 0..3 {
+            engine.tick();
+        }
+        assert!(engine.snake.contains(crossing));
+        for _ in 0..4 {
+            engine.tick();
+        }
+        assert!(!engine.snake.contains(crossing));
+        assert!(engine.game.state == GameState::Running);
+    }
+
+    #[test]
+    fn the_magnet_pulls_the_food_in_range() {
+        let mut engine = engine_with(PowerUp::Magnet);
+        engine.point = Some(Point::new(Cell::new(15, 10)));
+        engine.special = Some(Point {
+            kind: FoodKind::Bonus,
+            ..Point::new(Cell::new(12 + MAGNET_RANGE, 10))
+        });
+
+        engine.tick();
+
+        assert_eq!(
+            engine.point.as_ref().map(|point| point.cell),
+            Some(Cell::new(14, 10))
+        );
+        assert_eq!(
+            engine.special.as_ref().map(|special| special.cell),
+            Some(Cell::new(12 + MAGNET_RANGE, 10))
+        );
+
+        // the poison is never pulled
+        engine.special = Some(Point {
+            kind: FoodKind::Poison,
+            ..Point::new(Cell::new(13, 12))
+        });
+        engine.tick();
+
+        assert_eq!(
+            engine.special.as_ref().map(|special| special.cell),
+            Some(Cell::new(13, 12))
+        );
+    }
+
+    /// The hash after every tick of the games played by the bot from
+    /// `seed`, a new game starting whenever one ends.
+    fn hashes(seed: u64) -> Vec<u64> {
+        let mut engine = Engine::new(Board::new(20, 12), Settings::default(), seed);
+        engine.start();
+
+        (0..300)
+            .map(|_| {
+                if let Some(direction) = bot::next_turn(&engine) {
+                    engine.queue_turn(direction);
+                }
+                let events = engine.tick();
+                let hash = engine.state_hash();
+
+                if events.game_over || events.won {
+                    engine.restart();
+                    engine.start();
+                }
+
+                hash
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_same_seed_plays_the_same_games() {
+        assert_eq!(hashes(42), hashes(42));
+        assert_ne!(hashes(42), hashes(43));
+    }
+
+    #[test]
+    fn a_new_game_is_seeded_from_the_last() {
+        let mut first = Engine::new(Board::new(20, 12), Settings::default(), 42);
+        let mut second = Engine::new(Board::new(20, 12), Settings::default(), 42);
+
+        first.restart();
+        second.restart();
+
+        assert_eq!(first.seed, second.seed);
+        assert_ne!(first.seed, 42);
+        assert_eq!(first.state_hash(), second.state_hash());
+    }
+
+    /// A 3 by 2 board inside its walls, the snake covering the 4 cells on
+    /// the left and about to eat on the top right one.
+    fn nearly_full_engine() -> Engine {
+        let mut engine = Engine::new(Board::new(5, 4), Settings::default(), 7);
+        engine.start();
+        engine.snake = Snake::from_cells(
+            vec![
+                Cell::new(2, 2),
+                Cell::new(1, 2),
+                Cell::new(1, 1),
+                Cell::new(2, 1),
+            ],
+            Direction::Right,
+        );
+        engine.snake.grow();
+        engine.point = Some(Point::new(Cell::new(3, 2)));
+        engine.special = None;
+        engine.power_up = None;
+
+        engine
+    }
+
+    #[test]
+    fn filling_the_board_wins() {
+        let mut engine = nearly_full_engine();
+        // the food is on the last free cell
+        engine.snake = Snake::from_cells(
+            vec![
+                Cell::new(2, 2),
+                Cell::new(1, 2),
+                Cell::new(1, 1),
+                Cell::new(2, 1),
+                Cell::new(3, 1),
+            ],
+            Direction::Right,
+        );
+        engine.snake.grow();
+
+        let events = engine.tick();
+
+        assert!(events.won);
+        assert!(engine.game.state == GameState::Won);
+        assert!(engine.point.is_none());
+    }
+
+    #[test]
+    fn a_power_up_on_the_last_free_cell_does_not_win() {
+        let mut engine = nearly_full_engine();
+        engine.power_up = Some(Pickup {
+            cell: Cell::new(3, 1),
+            power_up: PowerUp::Ghost,
+            expires_ms: u64::MAX,
+        });
+
+        let events = engine.tick();
+
+        assert!(!events.won);
+        assert!(engine.game.state == GameState::Running);
+        assert_eq!(
+            engine.point.as_ref().map(|point| point.cell),
+            Some(Cell::new(3, 1))
+        );
+    }
+
+    #[test]
+    fn applies_one_queued_turn_per_tick() {
+        let mut engine = running_engine();
+        engine.snake.change_direction(Direction::Right);
+
+        engine.queue_turn(Direction::Up);
+        engine.queue_turn(Direction::Left);
+
+        assert_eq!(engine.tick().turn, Some(Direction::Up));
+        assert_eq!(engine.tick().turn, Some(Direction::Left));
+        assert_eq!(engine.tick().turn, None);
+        assert_eq!(engine.snake.direction, Direction::Left);
+    }
+
+    #[test]
+    fn ignores_turns_back_onto_the_snake() {
+        let mut engine = running_engine();
+        engine.snake.change_direction(Direction::Right);
+
+        engine.queue_turn(Direction::Left);
+        engine.queue_turn(Direction::Right);
+        assert_eq!(engine.tick().turn, None);
+
+        // checked against the last turn queued, not the current direction
+        engine.queue_turn(Direction::Up);
+        engine.queue_turn(Direction::Down);
+        engine.queue_turn(Direction::Left);
+        assert_eq!(engine.tick().turn, Some(Direction::Up));
+        assert_eq!(engine.tick().turn, Some(Direction::Left));
+    }
+
+    #[test]
+    fn queues_a_few_turns_at_most() {
+        let mut engine = running_engine();
+        engine.snake.change_direction(Direction::Right);
+
+        for direction in [
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+        ] {
+            engine.queue_turn(direction);
+        }
+
+        let turns: Vec<_> = (0..4).map(|_| engine.tick().turn).collect();
+        assert_eq!(
+            turns,
+            [
+                Some(Direction::Up),
+                Some(Direction::Left),
+                Some(Direction::Down),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_turns_unless_running() {
+        let mut engine = running_engine();
+        engine.game.state = GameState::Paused;
+
+        engine.queue_turn(Direction::Up);
+        engine.game.state = GameState::Running;
+
+        assert_eq!(engine.tick().turn, None);
+    }
+}
@@ -1,6 +1,18 @@
-const SPEED: u8 = 8;
+use std::{collections::VecDeque, fmt, str::FromStr};
 
-#[derive(PartialEq)]
+use serde::{Deserialize, Serialize};
+
+use crate::point::FoodWeights;
+
+/// How many times per second the snake moves.
+const SPEED: u32 = 12;
+const STARTING_LENGTH: usize = 12;
+
+/// How many turns can be typed ahead of a snake.
+const MAX_QUEUED_TURNS: usize = 3;
+
+/// One of the four directions the snake can move to.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
@@ -8,52 +20,336 @@ pub enum Direction {
     Right,
 }
 
-#[derive(PartialEq)]
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    /// The direction that would make the snake turn back on itself.
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+}
+
+/// The turns typed ahead of a snake, applied one per tick.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TurnQueue(VecDeque<Direction>);
+
+impl TurnQueue {
+    /// Queues `direction` for a snake going toward `current`. The turn is
+    /// checked against the direction the snake will have once the turns
+    /// already queued are applied, so two quick presses can't reverse the
+    /// snake into its own body.
+    pub fn push(&mut self, direction: Direction, current: Direction) {
+        let current = self.0.back().copied().unwrap_or(current);
+
+        if self.0.len() < MAX_QUEUED_TURNS
+            && current != direction
+            && current != direction.opposite()
+        {
+            self.0.push_back(direction);
+        }
+    }
+
+    /// The turn to apply on this tick, if any.
+    pub fn pop(&mut self) -> Option<Direction> {
+        self.0.pop_front()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// The rules the game is played with.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Touching the walls or the snake itself ends the game.
+    #[default]
+    Classic,
+    /// There are no walls, leaving the board on one edge enters it again
+    /// from the opposite one. Only touching the snake itself ends the game.
+    Wrap,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 2] = [Mode::Classic, Mode::Wrap];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Classic => "classic",
+            Mode::Wrap => "wrap",
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Mode::ALL
+            .into_iter()
+            .find(|mode| mode.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = Mode::ALL.iter().map(|mode| mode.name()).collect();
+                format!(
+                    "unknown mode '{name}', expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// A preset of the starting speed and of how fast it increases.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Insane => "insane",
+        }
+    }
+
+    /// How many times per second the snake moves at the start.
+    pub fn speed(self) -> u32 {
+        match self {
+            Difficulty::Easy => 8,
+            Difficulty::Normal => SPEED,
+            Difficulty::Hard => 16,
+            Difficulty::Insane => 20,
+        }
+    }
+
+    /// How the speed increases during the game, if it does.
+    pub fn progression(self) -> Option<Progression> {
+        match self {
+            Difficulty::Easy | Difficulty::Normal => None,
+            Difficulty::Hard => Some(Progression {
+                foods_per_level: 5,
+                speed_step: 1,
+                max_speed: 30,
+            }),
+            Difficulty::Insane => Some(Progression {
+                foods_per_level: 3,
+                speed_step: 2,
+                max_speed: 40,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Difficulty::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = Difficulty::ALL.iter().map(|d| d.name()).collect();
+                format!(
+                    "unknown difficulty '{name}', expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// The snake goes up a level every `foods_per_level` foods eaten, and moves
+/// `speed_step` more times per second on every level, up to `max_speed`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Progression {
+    pub foods_per_level: u32,
+    pub speed_step: u32,
+    pub max_speed: u32,
+}
+
+impl Default for Progression {
+    fn default() -> Self {
+        Progression {
+            foods_per_level: 5,
+            speed_step: 1,
+            max_speed: 60,
+        }
+    }
+}
+
+impl Progression {
+    /// The level reached after eating `foods`, starting at 1.
+    pub fn level(&self, foods: u32) -> u32 {
+        foods / self.foods_per_level.max(1) + 1
+    }
+
+    /// The speed on `level` of a game started at `starting_speed`.
+    pub fn speed(&self, starting_speed: u32, level: u32) -> u32 {
+        let speed = starting_speed.saturating_add((level - 1).saturating_mul(self.speed_step));
+
+        speed.min(self.max_speed.max(starting_speed))
+    }
+}
+
+/// Everything that changes how a game plays, chosen before it starts.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Settings {
+    /// How many times per second the snake moves.
+    pub speed: u32,
+    /// How many cells long the snake is at the start.
+    pub starting_length: usize,
+    #[serde(default)]
+    pub mode: Mode,
+    /// How the speed increases, it stays the same if `None`.
+    #[serde(default)]
+    pub progression: Option<Progression>,
+    /// How often each special food appears.
+    #[serde(default = "FoodWeights::none")]
+    pub food: FoodWeights,
+    /// Whether power-ups appear, see [`crate::power_up`].
+    #[serde(default)]
+    pub power_ups: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            speed: SPEED,
+            starting_length: STARTING_LENGTH,
+            mode: Mode::default(),
+            progression: None,
+            food: FoodWeights::default(),
+            power_ups: true,
+        }
+    }
+}
+
+/// The screen the game is currently in.
+#[derive(PartialEq, Hash, Serialize, Deserialize)]
 pub enum GameState {
     Startup,
     Running,
     Paused,
     GameOver,
+    /// The snake filled the whole board.
+    Won,
 }
 
+/// Score, state, level and speed of the current game.
+#[derive(Serialize, Deserialize)]
 pub struct Game {
     pub score: i32,
+    /// How many foods were eaten, whatever they were worth.
+    #[serde(default)]
+    pub foods: u32,
     pub state: GameState,
-    pub frame_num: i32,
-    speed: u8,
+    speed: u32,
+    #[serde(default = "first_level")]
+    level: u32,
+    /// How many moves per second the slow foods took off, kept through the
+    /// next levels.
+    #[serde(default)]
+    slowdown: u32,
 }
 
 impl Game {
-    pub fn new() -> Self {
+    pub fn new(speed: u32) -> Self {
         Game {
             score: 0,
+            foods: 0,
             state: GameState::Startup,
-            frame_num: 0,
-            speed: SPEED,
+            speed,
+            level: first_level(),
+            slowdown: 0,
         }
     }
 
-    pub fn increase_score(&mut self) {
-        self.score += 1;
+    /// Counts a food eaten, worth `points`.
+    pub fn eat(&mut self, points: i32) {
+        self.foods += 1;
+        self.score += points;
     }
 
-    pub fn increase_frame_num(&mut self) {
-        self.frame_num += {
-            if self.frame_num > 10 / (self.speed as i32) {
-                -self.frame_num
-            } else {
-                1
-            }
-        };
+    /// Makes the snake move `by` times per second less, but at least once.
+    pub fn slow_down(&mut self, by: u32) {
+        self.slowdown += by;
+        self.speed = self.speed.saturating_sub(by).max(1);
+    }
+
+    /// The number of simulation ticks per second.
+    pub fn speed(&self) -> u32 {
+        self.speed
+    }
+
+    /// The current level, starting at 1. It only changes with a
+    /// [`Progression`].
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Goes to `level`, where the snake moves `speed` times per second
+    /// before the slow foods eaten so far.
+    pub fn level_up(&mut self, level: u32, speed: u32) {
+        self.level = level;
+        self.speed = speed.saturating_sub(self.slowdown).max(1);
     }
 
     pub fn game_over(&mut self) {
         self.state = GameState::GameOver;
     }
 
-    pub fn restart(&mut self) {
+    pub fn win(&mut self) {
+        self.state = GameState::Won;
+    }
+
+    /// Starts over from the first level at `speed`.
+    pub fn restart(&mut self, speed: u32) {
         self.score = 0;
+        self.foods = 0;
         self.state = GameState::Running;
-        self.frame_num = 0;
+        self.speed = speed;
+        self.level = first_level();
+        self.slowdown = 0;
     }
 }
+
+fn first_level() -> u32 {
+    1
+}
@@ -0,0 +1,151 @@
+use std::collections::{HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::Direction;
+
+/// A single cell of the board, `(0, 0)` being the bottom left corner.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct Cell {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Cell {
+    pub fn new(x: i32, y: i32) -> Self {
+        Cell { x, y }
+    }
+
+    /// The cell next to this one going toward `direction`.
+    pub fn neighbour(&self, direction: Direction) -> Cell {
+        match direction {
+            Direction::Up => Cell::new(self.x, self.y + 1),
+            Direction::Down => Cell::new(self.x, self.y - 1),
+            Direction::Left => Cell::new(self.x - 1, self.y),
+            Direction::Right => Cell::new(self.x + 1, self.y),
+        }
+    }
+}
+
+/// The size of the board in cells, walls included.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Board {
+    pub width: i32,
+    pub height: i32,
+    /// Whether leaving the board on one edge enters it again from the
+    /// opposite one.
+    #[serde(default)]
+    pub wraps: bool,
+}
+
+impl Board {
+    pub fn new(width: i32, height: i32) -> Self {
+        Board {
+            width,
+            height,
+            wraps: false,
+        }
+    }
+
+    /// The same board, wrapping around its edges or not.
+    pub fn with_wrap(self, wraps: bool) -> Self {
+        Board { wraps, ..self }
+    }
+
+    /// The cell next to `cell` going toward `direction`, on the opposite
+    /// edge if the board wraps and `cell` is on the edge.
+    pub fn neighbour(&self, cell: Cell, direction: Direction) -> Cell {
+        let next = cell.neighbour(direction);
+
+        if self.wraps {
+            Cell::new(
+                next.x.rem_euclid(self.width),
+                next.y.rem_euclid(self.height),
+            )
+        } else {
+            next
+        }
+    }
+
+    /// The cell in the middle of the board.
+    pub fn center(&self) -> Cell {
+        Cell::new(self.width / 2, self.height / 2)
+    }
+
+    pub fn contains(&self, cell: Cell) -> bool {
+        (0..self.width).contains(&cell.x) && (0..self.height).contains(&cell.y)
+    }
+
+    /// Whether `cell` is on the outermost ring of the board.
+    pub fn is_edge(&self, cell: Cell) -> bool {
+        cell.x == 0 || cell.y == 0 || cell.x == self.width - 1 || cell.y == self.height - 1
+    }
+
+    /// Every cell of the board that can be reached from `start` without
+    /// going through a `blocked` one, in breadth first order.
+    pub fn flood_fill(&self, start: Cell, blocked: impl Fn(Cell) -> bool) -> Vec<Cell> {
+        let mut visited = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        let mut cells = Vec::new();
+
+        while let Some(cell) = queue.pop_front() {
+            cells.push(cell);
+
+            for direction in Direction::ALL {
+                let next = self.neighbour(cell, direction);
+
+                if self.contains(next) && !blocked(next) && visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_around_every_edge() {
+        let board = Board::new(10, 6).with_wrap(true);
+
+        assert_eq!(
+            board.neighbour(Cell::new(4, 5), Direction::Up),
+            Cell::new(4, 0)
+        );
+        assert_eq!(
+            board.neighbour(Cell::new(4, 0), Direction::Down),
+            Cell::new(4, 5)
+        );
+        assert_eq!(
+            board.neighbour(Cell::new(0, 3), Direction::Left),
+            Cell::new(9, 3)
+        );
+        assert_eq!(
+            board.neighbour(Cell::new(9, 3), Direction::Right),
+            Cell::new(0, 3)
+        );
+        // away from the edges it is the next cell
+        assert_eq!(
+            board.neighbour(Cell::new(4, 3), Direction::Right),
+            Cell::new(5, 3)
+        );
+    }
+
+    #[test]
+    fn only_wraps_if_asked() {
+        let board = Board::new(10, 6);
+
+        assert_eq!(
+            board.neighbour(Cell::new(9, 3), Direction::Right),
+            Cell::new(10, 3)
+        );
+        assert_eq!(
+            board.neighbour(Cell::new(4, 0), Direction::Down),
+            Cell::new(4, -1)
+        );
+    }
+}
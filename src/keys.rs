@@ -0,0 +1,542 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{de, Deserialize, Deserializer};
+
+use crate::game::Direction;
+
+/// Something the player can do with a key.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    #[serde(rename = "up")]
+    TurnUp,
+    #[serde(rename = "down")]
+    TurnDown,
+    #[serde(rename = "left")]
+    TurnLeft,
+    #[serde(rename = "right")]
+    TurnRight,
+    Pause,
+    Restart,
+    Quit,
+    /// Opens the key remapping screen.
+    Remap,
+}
+
+impl Action {
+    pub const ALL: [Action; 8] = [
+        Action::TurnUp,
+        Action::TurnDown,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::Pause,
+        Action::Restart,
+        Action::Quit,
+        Action::Remap,
+    ];
+
+    /// The name of the action in the `[keys]` table of the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::TurnUp => "up",
+            Action::TurnDown => "down",
+            Action::TurnLeft => "left",
+            Action::TurnRight => "right",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::Quit => "quit",
+            Action::Remap => "remap",
+        }
+    }
+
+    /// Whether the action always keeps a key, or the player couldn't leave
+    /// the game or get back to the remapping screen.
+    pub fn is_required(self) -> bool {
+        matches!(self, Action::Quit | Action::Remap)
+    }
+
+    /// Where the snake turns to, `None` for the actions other than turns.
+    pub fn direction(self) -> Option<Direction> {
+        match self {
+            Action::TurnUp => Some(Direction::Up),
+            Action::TurnDown => Some(Direction::Down),
+            Action::TurnLeft => Some(Direction::Left),
+            Action::TurnRight => Some(Direction::Right),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A key with its modifiers, e.g. `ctrl+c`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // shift is already part of the character typed, e.g. `R`, and some
+        // terminals report it while others don't
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => Key {
+                code: KeyCode::Char(c.to_ascii_uppercase()),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            _ => Key { code, modifiers },
+        }
+    }
+
+    pub fn plain(code: KeyCode) -> Self {
+        Key::new(code, KeyModifiers::NONE)
+    }
+
+    fn char(c: char) -> Self {
+        Key::plain(KeyCode::Char(c))
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Key::new(event.code, event.modifiers)
+    }
+}
+
+/// The keys written with a name, besides the function keys `f1`, `f2`...
+const NAMED_KEYS: [(&str, KeyCode); 16] = [
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("esc", KeyCode::Esc),
+    ("enter", KeyCode::Enter),
+    ("space", KeyCode::Char(' ')),
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+];
+
+impl Key {
+    /// Whether the key can be written in the config file and read back,
+    /// which isn't the case of keys like caps lock or the media keys.
+    pub fn can_be_saved(&self) -> bool {
+        self.to_string().parse() == Ok(*self)
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    /// Reads a key like `w`, `up`, `f5` or `ctrl+shift+x`. The named keys
+    /// are the ones of [`NAMED_KEYS`] and the function keys, the modifiers
+    /// `ctrl`, `alt` and `shift`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = name;
+
+        while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{modifier}' in key '{name}'")),
+            };
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => {
+                let lowercase = rest.to_lowercase();
+                let named = NAMED_KEYS
+                    .iter()
+                    .find(|(key, _)| *key == lowercase)
+                    .map(|&(_, code)| code);
+                let function = lowercase
+                    .strip_prefix('f')
+                    .and_then(|n| n.parse().ok())
+                    .filter(|&n| n > 0)
+                    .map(KeyCode::F);
+
+                named
+                    .or(function)
+                    .ok_or_else(|| format!("unknown key '{name}'"))?
+            }
+        };
+
+        Ok(Key::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            f.write_str("shift+")?;
+        }
+
+        if let Some((name, _)) = NAMED_KEYS.iter().find(|(_, code)| *code == self.code) {
+            return f.write_str(name);
+        }
+
+        match self.code {
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            // not readable back, see `Key::can_be_saved`
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Which keys trigger which [`Action`]. A key triggers at most one action.
+#[derive(Clone, PartialEq, Debug)]
+pub struct KeyBindings {
+    keys: HashMap<Action, Vec<Key>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let keys = HashMap::from([
+            (
+                Action::TurnUp,
+                vec![Key::char('w'), Key::char('k'), Key::plain(KeyCode::Up)],
+            ),
+            (
+                Action::TurnDown,
+                vec![Key::char('s'), Key::char('j'), Key::plain(KeyCode::Down)],
+            ),
+            (
+                Action::TurnLeft,
+                vec![Key::char('a'), Key::char('h'), Key::plain(KeyCode::Left)],
+            ),
+            (
+                Action::TurnRight,
+                vec![Key::char('d'), Key::char('l'), Key::plain(KeyCode::Right)],
+            ),
+            (Action::Pause, vec![Key::char('p')]),
+            (Action::Restart, vec![Key::char('r'), Key::char('R')]),
+            (Action::Quit, vec![Key::char('q'), Key::plain(KeyCode::Esc)]),
+            (Action::Remap, vec![Key::plain(KeyCode::F(2))]),
+        ]);
+
+        KeyBindings { keys }
+    }
+}
+
+impl KeyBindings {
+    /// The player and the turn of `event` when two players share the
+    /// keyboard: the arrows for the second one, the other keys bound to the
+    /// turns for the first one.
+    pub fn split_turn(&self, event: KeyEvent) -> Option<(usize, Action)> {
+        self.split_turn_of(Key::from(event))
+    }
+
+    pub fn split_turn_of(&self, key: Key) -> Option<(usize, Action)> {
+        let arrow = match key.code {
+            _ if !key.modifiers.is_empty() => None,
+            KeyCode::Up => Some(Action::TurnUp),
+            KeyCode::Down => Some(Action::TurnDown),
+            KeyCode::Left => Some(Action::TurnLeft),
+            KeyCode::Right => Some(Action::TurnRight),
+            _ => None,
+        };
+
+        match arrow {
+            Some(action) => Some((1, action)),
+            None => self
+                .action_of(key)
+                .filter(|action| action.direction().is_some())
+                .map(|action| (0, action)),
+        }
+    }
+
+    /// The keys of the first player when two share the keyboard, see
+    /// [`KeyBindings::split_turn`], like `w/a/s/d`.
+    pub fn first_player_keys(&self) -> String {
+        [
+            Action::TurnUp,
+            Action::TurnLeft,
+            Action::TurnDown,
+            Action::TurnRight,
+        ]
+        .into_iter()
+        .map(|action| {
+            self.keys(action)
+                .iter()
+                .find(|&&key| self.split_turn_of(key) == Some((0, action)))
+                .map_or_else(|| "-".to_string(), Key::to_string)
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+    }
+
+    /// The action triggered by `event`, if any.
+    pub fn action(&self, event: KeyEvent) -> Option<Action> {
+        self.action_of(Key::from(event))
+    }
+
+    pub fn action_of(&self, key: Key) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|action| self.keys(*action).contains(&key))
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.keys.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Binds `key` to `action` as well, unless it already triggers another
+    /// action, which is returned as the error.
+    pub fn bind(&mut self, action: Action, key: Key) -> Result<(), Action> {
+        match self.action_of(key) {
+            Some(other) if other != action => Err(other),
+            Some(_) => Ok(()),
+            None => {
+                self.keys.entry(action).or_default().push(key);
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes every key of `action`.
+    pub fn clear(&mut self, action: Action) {
+        self.keys.remove(&action);
+    }
+
+    /// Removes every key of `action` but the last one bound.
+    pub fn keep_last(&mut self, action: Action) {
+        if let Some(keys) = self.keys.get_mut(&action) {
+            let last = keys.len().saturating_sub(1);
+            keys.drain(..last);
+        }
+    }
+
+    /// Replaces the keys of the actions in `overrides`, checking that no key
+    /// ends up bound to two actions.
+    pub fn with_overrides(&self, overrides: HashMap<Action, Vec<Key>>) -> Result<Self, String> {
+        let mut bindings = self.clone();
+        for (action, keys) in &overrides {
+            if action.is_required() && keys.is_empty() {
+                return Err(format!("'{action}' needs at least one key"));
+            }
+            bindings.clear(*action);
+        }
+
+        for action in Action::ALL {
+            for key in overrides.get(&action).into_iter().flatten() {
+                bindings.bind(action, *key).map_err(|other| {
+                    format!("key '{key}' is bound to both '{other}' and '{action}'")
+                })?;
+            }
+        }
+
+        Ok(bindings)
+    }
+}
+
+/// The state of the screen where the keys are remapped.
+#[derive(Default)]
+pub struct Remapper {
+    /// The index in [`Action::ALL`] of the highlighted action.
+    pub selected: usize,
+    /// Whether the next key pressed is bound to the selected action.
+    pub capturing: bool,
+    /// The outcome of the last change, e.g. a conflict.
+    pub message: Option<String>,
+}
+
+impl Remapper {
+    pub fn selected_action(&self) -> Action {
+        Action::ALL[self.selected]
+    }
+
+    /// Handles a key pressed on the screen, returns `false` once the player
+    /// leaves it. Up and down pick an action, enter adds a key to it,
+    /// backspace removes its keys and esc closes the screen. Quit and remap
+    /// keep their last key.
+    pub fn handle_key(&mut self, event: KeyEvent, bindings: &mut KeyBindings) -> bool {
+        let action = self.selected_action();
+
+        if self.capturing {
+            let key = Key::from(event);
+
+            self.capturing = false;
+            if !key.can_be_saved() {
+                self.message = Some(format!("'{key}' can't be saved in the config file"));
+                return true;
+            }
+            self.message = Some(match bindings.bind(action, key) {
+                Ok(()) => format!("'{key}' now triggers '{action}'"),
+                Err(other) => format!("'{key}' is already bound to '{other}'"),
+            });
+
+            return true;
+        }
+
+        match event.code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(Action::ALL.len() - 1),
+            KeyCode::Enter => {
+                self.capturing = true;
+                self.message = Some(format!("Press the key to bind to '{action}'"));
+            }
+            KeyCode::Backspace | KeyCode::Delete if action.is_required() => {
+                bindings.keep_last(action);
+                self.message = Some(match bindings.keys(action) {
+                    [key] => format!("'{action}' needs a key, '{key}' is left"),
+                    _ => format!("'{action}' has no key"),
+                });
+            }
+            KeyCode::Backspace | KeyCode::Delete => {
+                bindings.clear(action);
+                self.message = Some(format!("'{action}' has no key anymore"));
+            }
+            KeyCode::Esc => return false,
+            _ => {}
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_the_turns_between_two_players() {
+        let mut bindings = KeyBindings::default();
+
+        assert_eq!(
+            bindings.split_turn_of(Key::char('w')),
+            Some((0, Action::TurnUp))
+        );
+        assert_eq!(
+            bindings.split_turn_of(Key::char('h')),
+            Some((0, Action::TurnLeft))
+        );
+        assert_eq!(
+            bindings.split_turn_of(Key::plain(KeyCode::Up)),
+            Some((1, Action::TurnUp))
+        );
+        assert_eq!(bindings.split_turn_of(Key::char('q')), None);
+        assert_eq!(bindings.first_player_keys(), "w/a/s/d");
+
+        bindings.keys.insert(Action::TurnUp, vec![Key::char('i')]);
+        assert_eq!(
+            bindings.split_turn_of(Key::char('i')),
+            Some((0, Action::TurnUp))
+        );
+        assert_eq!(bindings.split_turn_of(Key::char('w')), None);
+        assert_eq!(bindings.first_player_keys(), "i/a/s/d");
+    }
+
+    #[test]
+    fn keys_round_trip_through_their_names() {
+        let modifiers = [
+            KeyModifiers::NONE,
+            KeyModifiers::CONTROL,
+            KeyModifiers::ALT | KeyModifiers::SHIFT,
+        ];
+        let codes = NAMED_KEYS
+            .iter()
+            .map(|&(_, code)| code)
+            .chain([1, 12, 13, 24].map(KeyCode::F))
+            .chain(['w', 'R', '+', '1', ';'].map(KeyCode::Char));
+
+        for code in codes {
+            for modifiers in modifiers {
+                let key = Key::new(code, modifiers);
+
+                assert_eq!(key.to_string().parse(), Ok(key), "{key}");
+                assert!(key.can_be_saved());
+            }
+        }
+    }
+
+    #[test]
+    fn keys_without_a_name_can_not_be_saved() {
+        assert!(!Key::plain(KeyCode::CapsLock).can_be_saved());
+        assert!(!Key::plain(KeyCode::Null).can_be_saved());
+    }
+
+    #[test]
+    fn parses_keys_with_modifiers() {
+        assert_eq!(
+            "Ctrl+PageUp".parse(),
+            Ok(Key::new(KeyCode::PageUp, KeyModifiers::CONTROL))
+        );
+        assert_eq!("shift+r".parse(), Ok(Key::char('R')));
+        assert!("hyper+x".parse::<Key>().is_err());
+        assert!("f0".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn remapping_rejects_keys_that_can_not_be_saved() {
+        let mut bindings = KeyBindings::default();
+        let mut remapper = Remapper {
+            capturing: true,
+            ..Remapper::default()
+        };
+
+        remapper.handle_key(KeyEvent::from(KeyCode::CapsLock), &mut bindings);
+
+        assert_eq!(bindings, KeyBindings::default());
+    }
+
+    #[test]
+    fn remapping_keeps_a_key_to_quit_and_remap() {
+        let mut bindings = KeyBindings::default();
+        let mut remapper = Remapper {
+            selected: Action::ALL
+                .iter()
+                .position(|&action| action == Action::Quit)
+                .unwrap(),
+            ..Remapper::default()
+        };
+
+        remapper.handle_key(KeyEvent::from(KeyCode::Backspace), &mut bindings);
+        assert_eq!(bindings.keys(Action::Quit), [Key::plain(KeyCode::Esc)]);
+
+        remapper.handle_key(KeyEvent::from(KeyCode::Backspace), &mut bindings);
+        assert_eq!(bindings.keys(Action::Quit), [Key::plain(KeyCode::Esc)]);
+
+        remapper.selected = 0;
+        remapper.handle_key(KeyEvent::from(KeyCode::Backspace), &mut bindings);
+        assert!(bindings.keys(Action::TurnUp).is_empty());
+    }
+
+    #[test]
+    fn overrides_can_not_leave_quit_without_a_key() {
+        let overrides = HashMap::from([(Action::Quit, Vec::new())]);
+
+        assert!(KeyBindings::default().with_overrides(overrides).is_err());
+    }
+}
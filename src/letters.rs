@@ -3,9 +3,12 @@ use ratatui::{
     widgets::canvas::{Line, Painter, Shape},
 };
 
+/// A single letter drawn with lines on the canvas, 4 units wide and 5
+/// units tall before scaling.
 pub struct Letter {
     pub lines: Vec<Vec<f64>>,
     pub starting_x: f64,
+    pub starting_y: f64,
     pub scale: u8,
     pub color: Color,
 }
@@ -15,9 +18,9 @@ impl Shape for Letter {
         for line_points in &self.lines {
             let line = Line::new(
                 line_points[0] * (self.scale as f64) + self.starting_x,
-                line_points[1] * (self.scale as f64),
+                line_points[1] * (self.scale as f64) + self.starting_y,
                 line_points[2] * (self.scale as f64) + self.starting_x,
-                line_points[3] * (self.scale as f64),
+                line_points[3] * (self.scale as f64) + self.starting_y,
                 self.color,
             );
             line.draw(painter);
@@ -26,7 +29,7 @@ impl Shape for Letter {
 }
 
 impl Letter {
-    pub fn new_letter(letter: char, starting_x: f64) -> Self {
+    pub fn new_letter(letter: char, starting_x: f64, starting_y: f64) -> Self {
         let lines = match letter {
             // A
             'a' => vec![
@@ -215,27 +218,36 @@ impl Letter {
         Letter {
             lines,
             starting_x,
+            starting_y,
             scale: 1,
             color: Color::White,
         }
     }
 }
 
+/// A lowercase word drawn on the canvas as big letters, starting at
+/// `(starting_x, starting_y)` as their bottom left corner.
 pub struct Word {
     pub word: String,
     pub starting_x: f64,
+    pub starting_y: f64,
 }
 
 impl Word {
-    pub fn new(word: String, starting_x: f64) -> Self {
-        Word { word, starting_x }
+    pub fn new(word: String, starting_x: f64, starting_y: f64) -> Self {
+        Word {
+            word,
+            starting_x,
+            starting_y,
+        }
     }
 }
 
 impl Shape for Word {
     fn draw(&self, painter: &mut Painter) {
         for (i, letter) in self.word.chars().enumerate() {
-            let letter = Letter::new_letter(letter, self.starting_x + (i as f64) * 7.0);
+            let letter =
+                Letter::new_letter(letter, self.starting_x + (i as f64) * 7.0, self.starting_y);
 
             letter.draw(painter);
         }
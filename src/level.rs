@@ -0,0 +1,269 @@
+use std::{fmt, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::Engine,
+    game::Direction,
+    grid::{Board, Cell},
+};
+
+/// A board drawn in a plain text file, one character per cell, the first
+/// line being the top of the board:
+///
+/// ```text
+/// ; lines starting with a semicolon are comments
+/// ##########
+/// #........#
+/// #..>.....#
+/// #....##..#
+/// #..****..#
+/// ##########
+/// ```
+///
+/// - `#` is a wall,
+/// - `.` or a space is an empty cell,
+/// - `>`, `<`, `^` or `v` is where the head of the snake starts and where it
+///   is going, the middle of the board going right if missing,
+/// - `*` is a cell the food can spawn on, anywhere free if there is none.
+///
+/// Shorter lines are padded with empty cells.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub board: Board,
+    pub walls: Vec<Cell>,
+    pub spawn: Cell,
+    pub direction: Direction,
+    /// The cells the food spawns on, any free cell if empty.
+    pub food: Vec<Cell>,
+    /// What clears the level, the game goes on until the snake dies if
+    /// `None`. Level files have none, it is up to the campaign.
+    #[serde(default)]
+    pub goal: Option<Goal>,
+}
+
+impl Level {
+    /// Reads the level file at `path`, named after the file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|error| format!("cannot read level {}: {error}", path.display()))?;
+        let name = path
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+
+        Level::parse(name, &content)
+            .map_err(|error| format!("invalid level {}: {error}", path.display()))
+    }
+
+    /// Parses the content of a level file. The error tells the line and the
+    /// column of the faulty character.
+    pub fn parse(name: String, content: &str) -> Result<Self, String> {
+        let rows: Vec<(usize, &str)> = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.starts_with(';'))
+            .map(|(i, line)| (i + 1, line.trim_end_matches('\r')))
+            .collect();
+        // blank lines around the map are not part of it
+        let first = rows.iter().position(|(_, row)| !row.trim().is_empty());
+        let last = rows.iter().rposition(|(_, row)| !row.trim().is_empty());
+        let rows = match (first, last) {
+            (Some(first), Some(last)) => &rows[first..=last],
+            _ => return Err("the level is empty".to_string()),
+        };
+
+        let width = rows
+            .iter()
+            .map(|(_, row)| row.chars().count())
+            .max()
+            .unwrap_or_default();
+        let board = Board::new(width as i32, rows.len() as i32);
+        let mut walls = Vec::new();
+        let mut food = Vec::new();
+        let mut spawn = None;
+
+        for (y, (line, row)) in rows.iter().rev().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let cell = Cell::new(x as i32, y as i32);
+                let direction = match c {
+                    '#' => {
+                        walls.push(cell);
+                        continue;
+                    }
+                    '*' => {
+                        food.push(cell);
+                        continue;
+                    }
+                    '.' | ' ' => continue,
+                    '>' => Direction::Right,
+                    '<' => Direction::Left,
+                    '^' => Direction::Up,
+                    'v' => Direction::Down,
+                    _ => {
+                        return Err(format!(
+                            "line {line}, column {}: unexpected character '{c}'",
+                            x + 1
+                        ))
+                    }
+                };
+
+                if spawn.is_some() {
+                    return Err(format!(
+                        "line {line}, column {}: the snake can only start once",
+                        x + 1
+                    ));
+                }
+                spawn = Some((cell, direction));
+            }
+        }
+
+        let (spawn, direction) = spawn.unwrap_or((board.center(), Direction::Right));
+
+        Ok(Level {
+            name,
+            board,
+            walls,
+            spawn,
+            direction,
+            food,
+            goal: None,
+        })
+    }
+
+    /// Writes the level to `path`, in the format read by [`Level::load`].
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_text())
+            .map_err(|error| format!("cannot write level {}: {error}", path.display()))
+    }
+
+    /// The level in the format read by [`Level::parse`].
+    pub fn to_text(&self) -> String {
+        let width = self.board.width.max(0) as usize;
+        let height = self.board.height.max(0) as usize;
+        let mut rows = vec![vec!['.'; width]; height];
+        let mut put = |cell: Cell, c: char| {
+            if self.board.contains(cell) {
+                rows[height - 1 - cell.y as usize][cell.x as usize] = c;
+            }
+        };
+
+        self.food.iter().for_each(|&cell| put(cell, '*'));
+        self.walls.iter().for_each(|&cell| put(cell, '#'));
+        put(
+            self.spawn,
+            match self.direction {
+                Direction::Up => '^',
+                Direction::Down => 'v',
+                Direction::Left => '<',
+                Direction::Right => '>',
+            },
+        );
+
+        rows.into_iter()
+            .map(|row| row.into_iter().chain(['\n']).collect::<String>())
+            .collect()
+    }
+
+    /// Checks that a snake of `length` cells can start on the level, laid
+    /// out straight behind its head.
+    pub fn check_fits(&self, length: usize) -> Result<(), String> {
+        let mut cell = self.spawn;
+
+        for _ in 0..length.max(1) {
+            if !self.board.contains(cell) || self.walls.contains(&cell) {
+                return Err(format!(
+                    "a snake of length {length} doesn't fit behind the start of level '{}'",
+                    self.name
+                ));
+            }
+            cell = cell.neighbour(self.direction.opposite());
+        }
+
+        Ok(())
+    }
+}
+
+/// What the snake has to do to clear a level.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Goal {
+    /// Eat this many foods.
+    Eat(u32),
+    /// Be this many cells long.
+    Length(usize),
+    /// Stay alive for this many seconds.
+    Survive(u64),
+}
+
+impl Goal {
+    pub fn is_reached(&self, engine: &Engine) -> bool {
+        match *self {
+            Goal::Eat(foods) => engine.game.foods >= foods,
+            Goal::Length(length) => engine.snake.len() >= length,
+            Goal::Survive(seconds) => engine.elapsed_ms >= seconds * 1000,
+        }
+    }
+
+    /// How far `engine` is from the goal, e.g. `3/10`.
+    pub fn progress(&self, engine: &Engine) -> String {
+        match *self {
+            Goal::Eat(foods) => format!("{}/{foods}", engine.game.foods),
+            Goal::Length(length) => format!("{}/{length}", engine.snake.len()),
+            Goal::Survive(seconds) => format!("{}/{seconds}s", engine.elapsed_ms / 1000),
+        }
+    }
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Goal::Eat(foods) => write!(f, "eat {foods} food"),
+            Goal::Length(length) => write!(f, "reach length {length}"),
+            Goal::Survive(seconds) => write!(f, "survive {seconds} seconds"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVELS: [&str; 6] = [
+        include_str!("../levels/pillars.txt"),
+        include_str!("../levels/campaign/01-box.txt"),
+        include_str!("../levels/campaign/02-pillars.txt"),
+        include_str!("../levels/campaign/03-corridors.txt"),
+        include_str!("../levels/campaign/04-cross.txt"),
+        include_str!("../levels/campaign/05-maze.txt"),
+    ];
+
+    #[test]
+    fn levels_round_trip_through_their_text() {
+        for content in LEVELS {
+            let level = Level::parse("level".to_string(), content).unwrap();
+            let text = level.to_text();
+            let parsed = Level::parse("level".to_string(), &text).unwrap();
+
+            assert_eq!(parsed, level);
+            assert_eq!(parsed.to_text(), text);
+        }
+    }
+
+    #[test]
+    fn reads_the_cells_from_the_bottom_row() {
+        let level = Level::parse("tiny".to_string(), "#####\n#*..#\n#.>.#\n#####\n").unwrap();
+
+        assert_eq!(level.board, Board::new(5, 4));
+        assert_eq!(level.spawn, Cell::new(2, 1));
+        assert_eq!(level.direction, Direction::Right);
+        assert_eq!(level.food, [Cell::new(1, 2)]);
+        assert_eq!(level.walls.len(), 14);
+    }
+
+    #[test]
+    fn rejects_two_starts_and_unknown_characters() {
+        assert!(Level::parse("twice".to_string(), "#####\n#>.<#\n#####\n").is_err());
+        assert!(Level::parse("unknown".to_string(), "###\n#?#\n###\n").is_err());
+    }
+}
@@ -0,0 +1,42 @@
+//! A snake game for the terminal built on top of [ratatui].
+//!
+//! The board is a grid of integer [`grid::Cell`]s and the crate is split
+//! between the simulation and its rendering:
+//!
+//! - [`engine::Engine`] owns the [`game::Game`], the [`snake::Snake`], the
+//!   [`point::Point`] to eat and the [`walls::Walls`], and advances them one
+//!   tick at a time without touching the terminal.
+//! - [`snake::Snake`], [`point::Point`], [`walls::Walls`] and
+//!   [`letters::Word`] implement the ratatui canvas [`Shape`] trait, and
+//!   [`ui::draw`] puts everything together into a frame.
+//! - [`app`] runs the screens of the game, reading the input and drawing on
+//!   any ratatui backend.
+//!
+//! [`Shape`]: ratatui::widgets::canvas::Shape
+
+pub mod app;
+pub mod arena;
+pub mod bot;
+pub mod campaign;
+pub mod config;
+pub mod editor;
+pub mod engine;
+pub mod game;
+pub mod grid;
+pub mod keys;
+pub mod letters;
+pub mod level;
+#[cfg(feature = "net")]
+pub mod net;
+pub mod point;
+pub mod power_up;
+pub mod replay;
+pub mod save;
+pub mod scores;
+pub mod snake;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+pub mod storage;
+pub mod timestep;
+pub mod ui;
+pub mod walls;
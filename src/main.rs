@@ -1,157 +1,348 @@
-use collision_detection::check_collisions;
+use clap::{error::ErrorKind, CommandFactory, Parser};
+use cli::{Cli, Command, GameArgs};
 use crossterm::{
-    event::{self, KeyCode, KeyEventKind},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
-use game::{Direction, Game, GameState};
-
-use letters::Word;
-use point::Point;
-use ratatui::{
-    prelude::{CrosstermBackend, Terminal},
-    symbols::Marker,
-    widgets::canvas::Canvas,
+use ratatui::prelude::{CrosstermBackend, Terminal};
+#[cfg(feature = "ssh")]
+use ratatui_snake::ssh::{self, SshServer};
+use ratatui_snake::{
+    app::{self, PlayMode, TerminalEvents},
+    arena::Arena,
+    campaign,
+    config::{self, Config},
+    editor::Editor,
+    engine::Engine,
+    game::Settings,
+    grid::Board,
+    level::Level,
+    replay::Replay,
+    scores::HighScores,
+    ui::Theme,
 };
-use snake::Snake;
-use std::io::{stdout, Result};
-use walls::Walls;
+use std::io::{stdout, Result, Stdout};
+#[cfg(feature = "net")]
+use {
+    ratatui_snake::net::{Connection, Server},
+    std::net::TcpListener,
+};
+
+mod cli;
 
-mod game;
-mod letters;
-mod point;
-mod snake;
-mod walls;
+type Tui = Terminal<CrosstermBackend<Stdout>>;
+
+/// The size of the board of a match over the network, unless given.
+#[cfg(feature = "net")]
+const NET_BOARD: Board = Board {
+    width: 60,
+    height: 30,
+    wraps: false,
+};
 
 fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let config = Config::load(cli.config.as_deref()).unwrap_or_else(|message| {
+        eprintln!("error: {message}");
+        std::process::exit(1);
+    });
+    let theme = config.theme();
+    let config_path = cli.config.clone().or_else(config::config_path);
+
+    let (game, mode) = match cli.command {
+        Some(Command::Scores) => return print_scores(),
+        Some(Command::Replay { file }) => {
+            // load the replay before taking over the terminal, so errors
+            // are readable
+            let replay = Replay::load(&file).unwrap_or_else(|error| {
+                Cli::command()
+                    .error(
+                        ErrorKind::Io,
+                        format!("cannot load replay {}: {error}", file.display()),
+                    )
+                    .exit()
+            });
+
+            return run(|terminal| {
+                app::watch(terminal, &mut TerminalEvents, replay, &theme).map(|_| Vec::new())
+            });
+        }
+        Some(Command::Campaign) => {
+            let settings = Settings {
+                starting_length: campaign::STARTING_LENGTH,
+                ..cli.game.settings(&config)
+            };
+
+            return run(|terminal| {
+                app::play_campaign(
+                    terminal,
+                    &mut TerminalEvents,
+                    &config,
+                    config_path.as_deref(),
+                    settings,
+                )
+            });
+        }
+        Some(Command::Edit {
+            file,
+            width,
+            height,
+        }) => {
+            let editor = if file.exists() {
+                Level::load(&file)
+                    .map(|level| Editor::from_level(&level))
+                    .unwrap_or_else(|message| Cli::command().error(ErrorKind::Io, message).exit())
+            } else {
+                let size = GameArgs {
+                    width,
+                    height,
+                    ..cli.game.clone()
+                };
+                let board = size
+                    .board(terminal_board(&theme)?, &config.game)
+                    .unwrap_or_else(|message| {
+                        Cli::command()
+                            .error(ErrorKind::ValueValidation, message)
+                            .exit()
+                    });
+                let name = file
+                    .file_stem()
+                    .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+
+                Editor::new(name, board)
+            };
+            let settings = cli.game.settings(&config);
+
+            return run(|terminal| {
+                app::edit(
+                    terminal,
+                    &mut TerminalEvents,
+                    &config,
+                    config_path.as_deref(),
+                    settings,
+                    &file,
+                    editor,
+                )
+            });
+        }
+        Some(Command::Versus { game, rounds }) => {
+            let game = game.or(cli.game);
+            let board = game
+                .arena_board(terminal_board(&theme)?, &config.game, 2)
+                .unwrap_or_else(|message| {
+                    Cli::command()
+                        .error(ErrorKind::ValueValidation, message)
+                        .exit()
+                });
+            let seed = game.seed.unwrap_or_else(rand::random);
+            let arena = Arena::new(board, game.settings(&config), 2, rounds, seed);
+
+            return run(|terminal| app::versus(terminal, &mut TerminalEvents, &config, arena));
+        }
+        #[cfg(feature = "net")]
+        Some(Command::Serve {
+            game,
+            addr,
+            players,
+            rounds,
+            input_delay,
+        }) => {
+            let game = game.or(cli.game);
+            let board = game
+                .arena_board(NET_BOARD, &config.game, players as usize)
+                .unwrap_or_else(|message| {
+                    Cli::command()
+                        .error(ErrorKind::ValueValidation, message)
+                        .exit()
+                });
+            let seed = game.seed.unwrap_or_else(rand::random);
+            let arena = Arena::new(
+                board,
+                game.settings(&config),
+                players as usize,
+                rounds,
+                seed,
+            );
+            let listener = TcpListener::bind(&addr)?;
+
+            println!(
+                "Waiting for {players} players on {}",
+                listener.local_addr()?
+            );
+            return Server::new(arena, input_delay).run(listener, |message| println!("{message}"));
+        }
+        #[cfg(feature = "net")]
+        Some(Command::Join { addr }) => {
+            let (connection, welcome) = Connection::join(&addr).unwrap_or_else(|error| {
+                eprintln!("error: cannot join {addr}: {error}");
+                std::process::exit(1);
+            });
+
+            return run(|terminal| {
+                app::join(terminal, &mut TerminalEvents, &config, connection, welcome)
+            });
+        }
+        #[cfg(feature = "net")]
+        Some(Command::Spectate { addr }) => {
+            let (connection, welcome) = Connection::spectate(&addr).unwrap_or_else(|error| {
+                eprintln!("error: cannot spectate {addr}: {error}");
+                std::process::exit(1);
+            });
+
+            return run(|terminal| {
+                app::spectate(
+                    terminal,
+                    &mut TerminalEvents,
+                    &config,
+                    connection,
+                    welcome.arena,
+                )
+            });
+        }
+        #[cfg(feature = "ssh")]
+        Some(Command::SshServe {
+            game,
+            addr,
+            max_sessions,
+        }) => {
+            let game = game.or(cli.game);
+            let level = game.level.as_ref().map(|path| {
+                Level::load(path)
+                    .and_then(|level| {
+                        level
+                            .check_fits(game.settings(&config).starting_length)
+                            .map(|_| level)
+                    })
+                    .unwrap_or_else(|message| Cli::command().error(ErrorKind::Io, message).exit())
+            });
+            let key = ssh::host_key_path()
+                .and_then(|path| ssh::host_key(&path))
+                .unwrap_or_else(|error| {
+                    eprintln!("error: cannot load the host key: {error}");
+                    std::process::exit(1);
+                });
+
+            let settings = game.settings(&config);
+            let game_config = config.game.clone();
+            // every session plays its own game, on a board the size of its
+            // terminal like a local one
+            let new_engine = move |terminal: Board| {
+                let seed = game.seed.unwrap_or_else(rand::random);
+
+                match &level {
+                    Some(level) => Ok(Engine::with_level(level.clone(), settings.clone(), seed)),
+                    None => game
+                        .board(terminal, &game_config)
+                        .map(|board| Engine::new(board, settings.clone(), seed)),
+                }
+            };
+            let server = SshServer::new(
+                Box::new(new_engine),
+                config.clone(),
+                max_sessions as usize,
+                |message| println!("{message}"),
+            );
+
+            return server.run(&addr, key);
+        }
+        Some(Command::Play(game)) => (game.or(cli.game), PlayMode::Player),
+        Some(Command::Bot(game)) => (game.or(cli.game), PlayMode::Bot),
+        None => (cli.game, PlayMode::Player),
+    };
+
+    let settings = game.settings(&config);
+    let seed = game.seed.unwrap_or_else(rand::random);
+    let engine = match &game.level {
+        Some(path) => {
+            let level = Level::load(path)
+                .and_then(|level| level.check_fits(settings.starting_length).map(|_| level))
+                .unwrap_or_else(|message| Cli::command().error(ErrorKind::Io, message).exit());
+
+            Engine::with_level(level, settings, seed)
+        }
+        None => {
+            let board = game
+                .board(terminal_board(&theme)?, &config.game)
+                .unwrap_or_else(|message| {
+                    Cli::command()
+                        .error(ErrorKind::ValueValidation, message)
+                        .exit()
+                });
+
+            Engine::new(board, settings, seed)
+        }
+    };
+
+    run(|terminal| {
+        app::play(
+            terminal,
+            &mut TerminalEvents,
+            &config,
+            config_path.as_deref(),
+            engine,
+            mode,
+        )
+    })
+}
+
+/// The size of the terminal in cells of the board, with the marker of
+/// `theme`.
+fn terminal_board(theme: &Theme) -> Result<Board> {
+    let (columns, rows) = crossterm::terminal::size()?;
+    let (cells_per_column, cells_per_row) = theme.cells_per_char();
+
+    Ok(Board::new(
+        (columns * cells_per_column) as i32,
+        (rows * cells_per_row) as i32,
+    ))
+}
+
+/// Takes over the terminal to run `app`, then restores it and prints the
+/// messages `app` returned.
+fn run(app: impl FnOnce(&mut Tui) -> Result<Vec<String>>) -> Result<()> {
     stdout().execute(EnterAlternateScreen)?;
     enable_raw_mode()?;
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
     terminal.clear()?;
 
-    // the size of the board
-    let size = terminal.size()?;
-    let height = size.height as f64;
-    let width = size.width as f64;
-
-    let mut game = Game::new();
-    let mut snake = Snake::new();
-    let mut point = Point::new(width, height);
-    let walls = Walls::new(width, height);
-
-    loop {
-        game.increase_frame_num();
-
-        let _ = terminal.draw(|frame| {
-            let area = frame.size();
-
-            if check_collisions(&snake.head, &snake.body) || check_collisions(&snake.head, &walls) {
-                game.game_over();
-            }
-
-            if check_collisions(&snake.head, &point) {
-                snake.grow();
-                game.increase_score();
-                point.create_new_point();
-            }
-
-            // movement
-            if game.state == GameState::Running && game.frame_num == 0 {
-                snake.move_snake();
-            }
-
-            frame.render_widget(
-                Canvas::default()
-                    .x_bounds([-width / 2.0, width / 2.0])
-                    .y_bounds([-height, height])
-                    .marker(Marker::HalfBlock)
-                    .paint(|ctx| {
-                        ctx.draw(&walls);
-
-                        ctx.layer();
-
-                        if game.state != GameState::Startup {
-                            ctx.print(
-                                -width / 2.0 + 3.0,
-                                height - 4.0,
-                                format!("Score: {}", game.score),
-                            );
-                        }
-
-                        ctx.layer();
-
-                        match game.state {
-                            GameState::Running | GameState::Paused => {
-                                ctx.draw(&snake);
-                                ctx.draw(&point);
-                            }
-                            GameState::GameOver => {
-                                ctx.draw(&Word::new("gameover".to_string(), -27.0));
-                                ctx.print(-9.0, -5.0, "Press R to restart");
-                            }
-                            GameState::Startup => {
-                                ctx.draw(&Word::new("ratatui snake".to_string(), -48.0));
-                                ctx.print(-15.0, -5.0, "Press any character to start");
-                            }
-                        }
-                    }),
-                area,
-            )
-        });
-
-        if event::poll(std::time::Duration::from_millis(16))? {
-            if let event::Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    if game.state == GameState::Startup {
-                        game.state = GameState::Running;
-                    } else {
-                        match key.code {
-                            KeyCode::Char('q') | KeyCode::Esc => {
-                                break;
-                            }
-                            KeyCode::Char('p') => {
-                                if game.state == GameState::Paused {
-                                    game.state = GameState::Running;
-                                } else if GameState::Running == game.state {
-                                    game.state = GameState::Paused;
-                                }
-                            }
-                            KeyCode::Char('a') | KeyCode::Char('h') | KeyCode::Left => {
-                                if snake.head.direction != Direction::Right {
-                                    snake.change_direction(Direction::Left);
-                                }
-                            }
-                            KeyCode::Char('d') | KeyCode::Char('l') | KeyCode::Right => {
-                                if snake.head.direction != Direction::Left {
-                                    snake.change_direction(Direction::Right);
-                                }
-                            }
-                            KeyCode::Char('w') | KeyCode::Char('k') | KeyCode::Up => {
-                                if snake.head.direction != Direction::Down {
-                                    snake.change_direction(Direction::Up);
-                                }
-                            }
-                            KeyCode::Char('s') | KeyCode::Char('j') | KeyCode::Down => {
-                                if snake.head.direction != Direction::Up {
-                                    snake.change_direction(Direction::Down);
-                                }
-                            }
-                            KeyCode::Char('r') | KeyCode::Char('R') => {
-                                if game.state == GameState::GameOver {
-                                    game.restart();
-                                    snake = snake::Snake::new();
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-            }
-        }
-    }
+    let messages = app(&mut terminal);
 
     stdout().execute(LeaveAlternateScreen)?;
     disable_raw_mode()?;
 
+    for message in messages? {
+        println!("{message}");
+    }
+
+    Ok(())
+}
+
+fn print_scores() -> Result<()> {
+    let high_scores = HighScores::load()?;
+
+    if high_scores.scores.is_empty() {
+        println!("No high scores yet");
+        return Ok(());
+    }
+
+    println!(
+        "{:>3}  {:<16}  {:>5}  {:>6}  {:>6}  {:<10}  {:<8}  Seed",
+        "", "Name", "Score", "Length", "Time", "Date", "Mode"
+    );
+    for (i, score) in high_scores.scores.iter().enumerate() {
+        println!(
+            "{:>3}  {:<16}  {:>5}  {:>6}  {:>3}:{:02}  {:<10}  {:<8}  {}",
+            format!("{}.", i + 1),
+            score.name,
+            score.score,
+            score.length,
+            score.duration / 60,
+            score.duration % 60,
+            score.date.format("%Y-%m-%d"),
+            score.mode,
+            score.seed
+        );
+    }
+
     Ok(())
 }
@@ -0,0 +1,782 @@
+//! Matches played over the network. The server runs the only [`Arena`]
+//! that matters and sends a [`Snapshot`] of it to every client on each
+//! tick, the clients only send the turns of their player. Spectators get
+//! the snapshots too but can't send anything that changes the match.
+//!
+//! Messages are JSON objects, one per line.
+//!
+//! Every turn is stamped with the frame it has to be applied on: the last
+//! frame the client received plus the input delay of the server. As long as
+//! a turn takes less than that to reach the server, it is applied on the
+//! same frame for everyone, however far they are from the server.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TryRecvError, TrySendError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arena::{Arena, ArenaState},
+    game::Direction,
+    grid::Cell,
+    point::Point,
+    snake::Snake,
+    timestep::FixedTimestep,
+};
+
+/// The port used when the address doesn't say.
+pub const DEFAULT_PORT: u16 = 7777;
+
+/// How many frames after being sent a turn is applied, by default.
+pub const INPUT_DELAY: u64 = 2;
+
+/// Bumped on every incompatible change to the messages.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// How long the outcome of a round stays on screen before the next one.
+const INTERMISSION: Duration = Duration::from_secs(3);
+
+/// How many frames later than expected a turn can be stamped for, to
+/// allow for a client a little ahead of the server. Turns stamped further
+/// ahead are ignored.
+const TURN_MARGIN: u64 = 2;
+
+/// How many turns of a player can wait for their frame at most.
+const MAX_PENDING_TURNS: usize = 8;
+
+/// How long a client waits for the server to answer its hello.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many messages can wait for a client to read them before it is
+/// dropped, a few seconds of snapshots.
+const OUTBOX_SIZE: usize = 64;
+
+#[derive(Serialize, Deserialize)]
+pub enum ClientMessage {
+    /// The first message of every client, which only watches the match if
+    /// `spectator`.
+    Hello {
+        version: u32,
+        #[serde(default)]
+        spectator: bool,
+    },
+    /// Turns the snake of the player on `frame`.
+    Turn { frame: u64, direction: Direction },
+    /// Starts the match without waiting for the missing players.
+    Start,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum ServerMessage {
+    Welcome(Box<Welcome>),
+    /// The server doesn't want the client, e.g. because the match is full.
+    Refused {
+        reason: String,
+    },
+    Snapshot(Snapshot),
+}
+
+/// The answer to a [`ClientMessage::Hello`].
+#[derive(Serialize, Deserialize)]
+pub struct Welcome {
+    /// The player the client plays, `None` for spectators.
+    pub player: Option<usize>,
+    pub input_delay: u64,
+    /// The match as it is when joining, updated by the snapshots.
+    pub arena: Arena,
+}
+
+/// What changes in a match from one tick to the next.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    /// How many ticks the server has run since it started, the turns are
+    /// stamped with.
+    pub frame: u64,
+    pub round: u32,
+    pub ticks: u64,
+    pub elapsed_ms: u64,
+    pub state: ArenaState,
+    pub point: Option<Cell>,
+    pub players: Vec<PlayerSnapshot>,
+    /// Whether someone plays each player.
+    pub connected: Vec<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PlayerSnapshot {
+    /// The cells of the snake, from the head to the tail.
+    pub cells: Vec<Cell>,
+    pub direction: Direction,
+    pub score: i32,
+    pub alive: bool,
+    pub wins: u32,
+}
+
+impl Snapshot {
+    pub fn new(arena: &Arena, frame: u64, connected: Vec<bool>) -> Self {
+        Snapshot {
+            frame,
+            round: arena.round,
+            ticks: arena.ticks,
+            elapsed_ms: arena.elapsed_ms,
+            state: arena.state,
+            point: arena.point.as_ref().map(|point| point.cell),
+            players: arena
+                .players
+                .iter()
+                .map(|player| PlayerSnapshot {
+                    cells: player.snake.cells().copied().collect(),
+                    direction: player.snake.direction,
+                    score: player.score,
+                    alive: player.alive,
+                    wins: player.wins,
+                })
+                .collect(),
+            connected,
+        }
+    }
+
+    /// Brings the copy of the match of a client up to date.
+    pub fn apply(&self, arena: &mut Arena) {
+        arena.round = self.round;
+        arena.ticks = self.ticks;
+        arena.elapsed_ms = self.elapsed_ms;
+        arena.state = self.state;
+        arena.point = self.point.map(Point::new);
+
+        for (contender, player) in arena.players.iter_mut().zip(&self.players) {
+            if !player.cells.is_empty() {
+                contender.snake = Snake::from_cells(player.cells.clone(), player.direction);
+            }
+            contender.score = player.score;
+            contender.alive = player.alive;
+            contender.wins = player.wins;
+        }
+    }
+}
+
+/// `addr` with the [`DEFAULT_PORT`] if it has none.
+pub fn with_default_port(addr: &str) -> String {
+    let has_port = addr
+        .rsplit_once(':')
+        .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok());
+
+    if has_port {
+        addr.to_string()
+    } else {
+        format!("{addr}:{DEFAULT_PORT}")
+    }
+}
+
+/// Writes `message` on its own line.
+fn send(stream: &mut TcpStream, message: &impl Serialize) -> io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+
+    stream.write_all(line.as_bytes())
+}
+
+/// Sends every message read from `stream` to `sender` until it closes,
+/// then `closed`.
+fn read_messages<M, E>(
+    stream: impl io::Read,
+    sender: Sender<E>,
+    message: impl Fn(M) -> E,
+    closed: E,
+) where
+    M: for<'de> Deserialize<'de>,
+{
+    for line in BufReader::new(stream).lines() {
+        // a garbled message ends the connection like a closed one
+        let Ok(parsed) = line
+            .map_err(|_| ())
+            .and_then(|line| serde_json::from_str::<M>(&line).map_err(|_| ()))
+        else {
+            break;
+        };
+        if sender.send(message(parsed)).is_err() {
+            return;
+        }
+    }
+
+    let _ = sender.send(closed);
+}
+
+/// The connection of a client to a server.
+pub struct Connection {
+    stream: TcpStream,
+    messages: Receiver<Option<ServerMessage>>,
+}
+
+impl Connection {
+    /// Joins the match of the server at `addr` as one of the players.
+    pub fn join(addr: &str) -> io::Result<(Connection, Welcome)> {
+        Connection::connect(addr, false)
+    }
+
+    /// Watches the match of the server at `addr`, without playing.
+    pub fn spectate(addr: &str) -> io::Result<(Connection, Welcome)> {
+        Connection::connect(addr, true)
+    }
+
+    fn connect(addr: &str, spectator: bool) -> io::Result<(Connection, Welcome)> {
+        let mut stream = TcpStream::connect(with_default_port(addr))?;
+        stream.set_nodelay(true)?;
+        send(
+            &mut stream,
+            &ClientMessage::Hello {
+                version: PROTOCOL_VERSION,
+                spectator,
+            },
+        )?;
+
+        // something that accepts connections but never answers isn't a
+        // server, it mustn't hang the client
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut line = String::new();
+        reader
+            .read_line(&mut line)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+                    io::Error::new(io::ErrorKind::TimedOut, "the server didn't answer")
+                }
+                _ => err,
+            })?;
+        // the snapshots come whenever the match ticks, however slowly
+        stream.set_read_timeout(None)?;
+
+        let welcome = match serde_json::from_str(&line)? {
+            ServerMessage::Welcome(welcome) => *welcome,
+            ServerMessage::Refused { reason } => return Err(io::Error::other(reason)),
+            ServerMessage::Snapshot(_) => {
+                return Err(io::Error::other("the server didn't welcome us"));
+            }
+        };
+
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || read_messages(reader, sender, Some, None));
+
+        Ok((Connection { stream, messages }, welcome))
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> io::Result<()> {
+        send(&mut self.stream, message)
+    }
+
+    /// The next message of the server if one arrived, an error once the
+    /// server closed the connection.
+    pub fn receive(&self) -> io::Result<Option<ServerMessage>> {
+        match self.messages.try_recv() {
+            Ok(Some(message)) => Ok(Some(message)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Ok(None) | Err(TryRecvError::Disconnected) => Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "the server closed the connection",
+            )),
+        }
+    }
+}
+
+/// What the threads of the server tell its main loop.
+enum Event {
+    Connected(TcpStream),
+    Message(u64, ClientMessage),
+    Disconnected(u64),
+}
+
+/// What a client does once it said hello.
+#[derive(Clone, Copy)]
+enum Role {
+    Player(usize),
+    Spectator,
+}
+
+struct Client {
+    stream: TcpStream,
+    /// The lines its writer thread sends, closed once the client is refused.
+    outbox: Option<SyncSender<Arc<str>>>,
+    /// `None` until the client said hello.
+    role: Option<Role>,
+}
+
+/// Writes the lines of `outbox` to `stream` until it closes or the client
+/// stops reading, then closes the connection.
+fn write_messages(mut stream: TcpStream, outbox: Receiver<Arc<str>>) {
+    for line in outbox {
+        if stream.write_all(line.as_bytes()).is_err() {
+            break;
+        }
+    }
+
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+/// Runs a match for the clients connecting to it, a player each, watched
+/// by any number of spectators.
+pub struct Server {
+    arena: Arena,
+    input_delay: u64,
+    clients: HashMap<u64, Client>,
+    /// The client playing each player.
+    slots: Vec<Option<u64>>,
+    /// The turns of each player waiting for their frame.
+    inputs: Vec<VecDeque<(u64, Direction)>>,
+    frame: u64,
+    /// When the current round or match ended.
+    ended_at: Option<Instant>,
+}
+
+impl Server {
+    /// A server for the match on `arena`, which starts once every player
+    /// joined or when one of them asks.
+    pub fn new(arena: Arena, input_delay: u64) -> Self {
+        let players = arena.players.len();
+
+        Server {
+            arena,
+            input_delay,
+            clients: HashMap::new(),
+            slots: vec![None; players],
+            inputs: vec![VecDeque::new(); players],
+            frame: 0,
+            ended_at: None,
+        }
+    }
+
+    /// Accepts clients on `listener` and runs the match forever, telling
+    /// `log` who comes and goes.
+    pub fn run(mut self, listener: TcpListener, mut log: impl FnMut(String)) -> io::Result<()> {
+        let (sender, events) = mpsc::channel();
+        let accepted = sender.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if accepted.send(Event::Connected(stream)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut timestep = FixedTimestep::new(self.arena.settings.speed);
+        let mut next_id = 0;
+
+        loop {
+            for _ in 0..timestep.ticks() {
+                self.step();
+            }
+
+            match events.recv_timeout(timestep.until_next_tick()) {
+                Ok(Event::Connected(stream)) => {
+                    let id = next_id;
+                    next_id += 1;
+
+                    // a client we can't talk to is left out, not the others
+                    if let Err(err) = self.connect(id, stream, sender.clone()) {
+                        log(format!("A client couldn't connect: {err}"));
+                    }
+                }
+                Ok(Event::Message(id, message)) => self.handle(id, message, &mut log),
+                Ok(Event::Disconnected(id)) => self.disconnect(id, &mut log),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+
+    /// Sets up the connection of the client `id` and starts reading and
+    /// writing its messages, each on their own thread.
+    fn connect(&mut self, id: u64, stream: TcpStream, sender: Sender<Event>) -> io::Result<()> {
+        stream.set_nodelay(true)?;
+
+        let reader = stream.try_clone()?;
+        let writer = stream.try_clone()?;
+        let (outbox, lines) = mpsc::sync_channel(OUTBOX_SIZE);
+
+        thread::spawn(move || write_messages(writer, lines));
+        thread::spawn(move || {
+            read_messages(
+                reader,
+                sender,
+                move |message| Event::Message(id, message),
+                Event::Disconnected(id),
+            )
+        });
+
+        let client = Client {
+            stream,
+            outbox: Some(outbox),
+            role: None,
+        };
+        self.clients.insert(id, client);
+
+        Ok(())
+    }
+
+    fn handle(&mut self, id: u64, message: ClientMessage, log: &mut impl FnMut(String)) {
+        let Some(client) = self.clients.get_mut(&id) else {
+            return;
+        };
+
+        match (message, client.role) {
+            (ClientMessage::Hello { version, spectator }, None) => {
+                // a client of another version couldn't even spectate
+                if version != PROTOCOL_VERSION {
+                    let reason = format!(
+                        "the server speaks version {PROTOCOL_VERSION} of the protocol, not {version}"
+                    );
+                    self.refuse(id, reason);
+                    return;
+                }
+                let free = self.slots.iter().position(Option::is_none);
+                let role = match free {
+                    _ if spectator => Role::Spectator,
+                    Some(player) => Role::Player(player),
+                    None => {
+                        let reason = "the match is full, spectate it instead".to_string();
+                        self.refuse(id, reason);
+                        return;
+                    }
+                };
+
+                let welcome = ServerMessage::Welcome(Box::new(Welcome {
+                    player: match role {
+                        Role::Player(player) => Some(player),
+                        Role::Spectator => None,
+                    },
+                    input_delay: self.input_delay,
+                    arena: self.arena.clone(),
+                }));
+
+                if !self.send_to(id, &welcome) {
+                    return;
+                }
+                let Some(client) = self.clients.get_mut(&id) else {
+                    return;
+                };
+                client.role = Some(role);
+
+                let addr = client
+                    .stream
+                    .peer_addr()
+                    .map_or_else(|_| "somewhere".to_string(), |addr| addr.to_string());
+
+                let Role::Player(player) = role else {
+                    log(format!("A spectator joined from {addr}"));
+                    return;
+                };
+                self.slots[player] = Some(id);
+                log(format!("Player {} joined from {addr}", player + 1));
+
+                // someone joining mid-round plays from the next one
+                if self.arena.state != ArenaState::Startup {
+                    self.arena.eliminate(player);
+                } else if self.slots.iter().all(Option::is_some) {
+                    self.start();
+                }
+            }
+            (ClientMessage::Turn { frame, direction }, Some(Role::Player(player))) => {
+                let inputs = &mut self.inputs[player];
+
+                if frame <= self.frame + self.input_delay + TURN_MARGIN
+                    && inputs.len() < MAX_PENDING_TURNS
+                {
+                    inputs.push_back((frame, direction));
+                }
+            }
+            (ClientMessage::Start, Some(Role::Player(_)))
+                if self.arena.state == ArenaState::Startup =>
+            {
+                self.start();
+            }
+            _ => {}
+        }
+    }
+
+    fn disconnect(&mut self, id: u64, log: &mut impl FnMut(String)) {
+        let Some(client) = self.clients.remove(&id) else {
+            return;
+        };
+        let player = match client.role {
+            Some(Role::Player(player)) => player,
+            Some(Role::Spectator) => {
+                log("A spectator left".to_string());
+                return;
+            }
+            None => return,
+        };
+
+        log(format!("Player {} left", player + 1));
+        self.slots[player] = None;
+        self.inputs[player].clear();
+        self.arena.eliminate(player);
+
+        // an empty server waits for a new match
+        if self.slots.iter().all(Option::is_none) {
+            self.arena.restart();
+            self.arena.state = ArenaState::Startup;
+            self.ended_at = None;
+        }
+    }
+
+    /// Tells `id` why it can't join, its writer then closes the
+    /// connection.
+    fn refuse(&mut self, id: u64, reason: String) {
+        self.send_to(id, &ServerMessage::Refused { reason });
+        if let Some(client) = self.clients.get_mut(&id) {
+            client.outbox = None;
+        }
+    }
+
+    /// Queues `message` for `id`, which is dropped if it has too many
+    /// waiting already. Returns whether it was queued.
+    fn send_to(&mut self, id: u64, message: &ServerMessage) -> bool {
+        match serde_json::to_string(message) {
+            Ok(line) => self.send_line(id, Arc::from(line + "\n")),
+            Err(_) => false,
+        }
+    }
+
+    fn send_line(&mut self, id: u64, line: Arc<str>) -> bool {
+        let Some(outbox) = self
+            .clients
+            .get(&id)
+            .and_then(|client| client.outbox.as_ref())
+        else {
+            return false;
+        };
+
+        match outbox.try_send(line) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.drop_client(id);
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+
+    /// Closes the connection of `id`, its reader then reports it gone.
+    fn drop_client(&mut self, id: u64) {
+        if let Some(client) = self.clients.get(&id) {
+            let _ = client.stream.shutdown(Shutdown::Both);
+        }
+    }
+
+    /// Starts playing, the players missing sitting the round out.
+    fn start(&mut self) {
+        self.arena.state = ArenaState::Running;
+        self.eliminate_absent();
+    }
+
+    fn eliminate_absent(&mut self) {
+        for player in 0..self.slots.len() {
+            if self.slots[player].is_none() {
+                self.arena.eliminate(player);
+            }
+            self.inputs[player].clear();
+        }
+    }
+
+    /// Runs one frame: a tick of the match, or the next round once the
+    /// outcome of the last one has been shown long enough. Every client is
+    /// then sent the new state.
+    fn step(&mut self) {
+        self.frame += 1;
+
+        match self.arena.state {
+            ArenaState::Running => {
+                for player in 0..self.inputs.len() {
+                    while let Some(&(frame, direction)) = self.inputs[player].front() {
+                        if frame > self.frame {
+                            break;
+                        }
+                        self.inputs[player].pop_front();
+                        self.arena.queue_turn(player, direction);
+                    }
+                }
+
+                if self.arena.tick().round_over {
+                    self.ended_at = Some(Instant::now());
+                }
+            }
+            ArenaState::RoundOver { .. } | ArenaState::MatchOver { .. }
+                if self
+                    .ended_at
+                    .is_none_or(|ended_at| ended_at.elapsed() >= INTERMISSION) =>
+            {
+                if matches!(self.arena.state, ArenaState::RoundOver { .. }) {
+                    self.arena.next_round();
+                } else {
+                    self.arena.restart();
+                }
+                self.ended_at = None;
+                self.eliminate_absent();
+            }
+            _ => {}
+        }
+
+        let connected = self.slots.iter().map(Option::is_some).collect();
+        let snapshot = ServerMessage::Snapshot(Snapshot::new(&self.arena, self.frame, connected));
+        let line: Arc<str> = match serde_json::to_string(&snapshot) {
+            Ok(line) => Arc::from(line + "\n"),
+            Err(_) => return,
+        };
+
+        let welcomed: Vec<u64> = self
+            .clients
+            .iter()
+            .filter(|(_, client)| client.role.is_some())
+            .map(|(&id, _)| id)
+            .collect();
+        for id in welcomed {
+            self.send_line(id, line.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game::Settings, grid::Board};
+
+    fn arena(players: usize) -> Arena {
+        Arena::new(Board::new(30, 10), Settings::default(), players, 3, 1)
+    }
+
+    /// A server for a match of two, player 1 connected and playing alone.
+    fn server() -> (Server, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        let mut server = Server::new(arena(2), INPUT_DELAY);
+        let (sender, _) = mpsc::channel();
+        server.connect(0, stream, sender).unwrap();
+        let hello = ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+            spectator: false,
+        };
+        server.handle(0, hello, &mut |_| {});
+        server.start();
+
+        (server, client)
+    }
+
+    fn turn(server: &mut Server, frame: u64, direction: Direction) {
+        server.handle(0, ClientMessage::Turn { frame, direction }, &mut |_| {});
+    }
+
+    #[test]
+    fn applies_the_turns_on_their_frame() {
+        let (mut server, _client) = server();
+
+        turn(&mut server, 3, Direction::Up);
+        server.step();
+        server.step();
+        assert_eq!(server.arena.players[0].snake.direction, Direction::Right);
+        assert_eq!(server.inputs[0].len(), 1);
+
+        server.step();
+        assert_eq!(server.arena.players[0].snake.direction, Direction::Up);
+        assert!(server.inputs[0].is_empty());
+    }
+
+    #[test]
+    fn ignores_the_turns_stamped_too_far_ahead() {
+        let (mut server, _client) = server();
+        let last = server.frame + INPUT_DELAY + TURN_MARGIN;
+
+        turn(&mut server, last + 1, Direction::Up);
+        assert!(server.inputs[0].is_empty());
+        turn(&mut server, last, Direction::Up);
+        assert_eq!(server.inputs[0].len(), 1);
+    }
+
+    #[test]
+    fn keeps_a_few_pending_turns_at_most() {
+        let (mut server, _client) = server();
+
+        let frame = server.frame + 1;
+        for _ in 0..MAX_PENDING_TURNS * 2 {
+            turn(&mut server, frame, Direction::Up);
+        }
+        assert_eq!(server.inputs[0].len(), MAX_PENDING_TURNS);
+    }
+
+    /// The message a server sends to a client saying hello with `version`.
+    fn answer(addr: &str, version: u32, spectator: bool) -> ServerMessage {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        send(&mut stream, &ClientMessage::Hello { version, spectator }).unwrap();
+
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[test]
+    fn welcomes_players_then_spectators() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || Server::new(arena(1), INPUT_DELAY).run(listener, |_| {}));
+
+        let (_player, welcome) = Connection::join(&addr).unwrap();
+        assert_eq!(welcome.player, Some(0));
+        assert_eq!(welcome.input_delay, INPUT_DELAY);
+
+        let full = Connection::join(&addr).err().unwrap();
+        assert!(full.to_string().contains("full"));
+        let (_spectator, welcome) = Connection::spectate(&addr).unwrap();
+        assert_eq!(welcome.player, None);
+
+        // even a full match tells an older client why it can't join
+        let ServerMessage::Refused { reason } = answer(&addr, PROTOCOL_VERSION - 1, false) else {
+            panic!("a client of another version was welcomed");
+        };
+        assert!(reason.contains("version"));
+        assert!(matches!(
+            answer(&addr, PROTOCOL_VERSION + 1, true),
+            ServerMessage::Refused { .. }
+        ));
+    }
+
+    #[test]
+    fn a_snapshot_brings_a_copy_up_to_date() {
+        let mut arena = arena(2);
+        let mut copy = arena.clone();
+        arena.state = ArenaState::Running;
+        arena.queue_turn(0, Direction::Up);
+        for _ in 0..5 {
+            arena.tick();
+        }
+        arena.players[1].wins = 2;
+
+        let json = serde_json::to_string(&Snapshot::new(&arena, 5, vec![true, false])).unwrap();
+        let snapshot: Snapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(snapshot.connected, [true, false]);
+        snapshot.apply(&mut copy);
+
+        assert_eq!(copy.state, arena.state);
+        assert_eq!(copy.ticks, arena.ticks);
+        assert_eq!(
+            copy.point.as_ref().map(|point| point.cell),
+            arena.point.as_ref().map(|point| point.cell)
+        );
+        for (copied, player) in copy.players.iter().zip(&arena.players) {
+            assert!(copied.snake.cells().eq(player.snake.cells()));
+            assert_eq!(copied.snake.direction, player.snake.direction);
+            assert_eq!(copied.score, player.score);
+            assert_eq!(copied.alive, player.alive);
+            assert_eq!(copied.wins, player.wins);
+        }
+    }
+}
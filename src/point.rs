@@ -1,54 +1,247 @@
-use collision_detection::{coord::Coord, Collidable};
+use std::collections::HashSet;
+
+use rand::Rng;
 use ratatui::{
     style::Color,
-    widgets::canvas::{Line, Shape},
+    widgets::canvas::{Points, Shape},
 };
+use serde::{Deserialize, Serialize};
 
-pub struct Point {
-    pub coord: Coord,
-    pub max_x: f64,
-    pub max_y: f64,
+use crate::{
+    grid::{Board, Cell},
+    snake::Snake,
+    ui::Painted,
+    walls::Walls,
+};
+
+/// How many points a bonus food is worth.
+pub const BONUS_SCORE: i32 = 5;
+
+/// How many cells a shrink food takes from the snake.
+pub const SHRINK_CELLS: usize = 3;
+
+/// How many moves per second a slow food takes from the snake.
+pub const SLOW_DOWN: u32 = 2;
+
+/// How long a special food stays on the board, in milliseconds of game
+/// time.
+pub const SPECIAL_LIFETIME_MS: u64 = 6000;
+
+/// What eating a food does.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FoodKind {
+    /// One more point and one more cell.
+    #[default]
+    Normal,
+    /// [`BONUS_SCORE`] more points and one more cell.
+    Bonus,
+    /// One more point and [`SHRINK_CELLS`] cells less.
+    Shrink,
+    /// One more point and [`SLOW_DOWN`] moves per second less.
+    Slow,
+    /// Ends the game.
+    Poison,
 }
 
-impl Point {
-    pub fn new(max_x: f64, max_y: f64) -> Self {
-        let coord = Point::get_random_coord(max_x, max_y);
+impl FoodKind {
+    pub fn color(self) -> Color {
+        match self {
+            FoodKind::Normal => Color::Red,
+            FoodKind::Bonus => Color::Yellow,
+            FoodKind::Shrink => Color::Cyan,
+            FoodKind::Slow => Color::Green,
+            FoodKind::Poison => Color::Magenta,
+        }
+    }
 
-        Point {
-            coord,
-            max_x,
-            max_y,
+    /// The character the food is drawn with, the normal one being a dot.
+    pub fn glyph(self) -> &'static str {
+        match self {
+            FoodKind::Normal => "•",
+            FoodKind::Bonus => "$",
+            FoodKind::Shrink => "-",
+            FoodKind::Slow => "~",
+            FoodKind::Poison => "x",
         }
     }
+}
 
-    pub fn get_random_coord(x_max: f64, y_max: f64) -> Coord {
-        let x = (rand::random::<f64>() * x_max - x_max / 2.0).round();
-        let y = (rand::random::<f64>() * y_max - y_max / 2.0).round();
+/// How likely each kind of special food is to appear after eating, relative
+/// to each other and to `nothing`, which is no special food at all.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FoodWeights {
+    pub nothing: u32,
+    pub bonus: u32,
+    pub shrink: u32,
+    pub slow: u32,
+    pub poison: u32,
+}
 
-        Coord::new(x, y)
+impl Default for FoodWeights {
+    fn default() -> Self {
+        FoodWeights {
+            nothing: 10,
+            bonus: 3,
+            shrink: 1,
+            slow: 1,
+            poison: 1,
+        }
     }
+}
 
-    pub fn create_new_point(&mut self) {
-        self.coord = Point::get_random_coord(self.max_x, self.max_y);
+impl FoodWeights {
+    /// Never any special food, like in the games recorded before they
+    /// existed.
+    pub fn none() -> Self {
+        FoodWeights {
+            nothing: 1,
+            bonus: 0,
+            shrink: 0,
+            slow: 0,
+            poison: 0,
+        }
+    }
+
+    /// Draws the kind of the special food to spawn, if any. The RNG is left
+    /// untouched when there can't be any, so games without special food play
+    /// the same as before they existed.
+    pub fn pick(&self, rng: &mut impl Rng) -> Option<FoodKind> {
+        let kinds = [
+            (FoodKind::Bonus, self.bonus),
+            (FoodKind::Shrink, self.shrink),
+            (FoodKind::Slow, self.slow),
+            (FoodKind::Poison, self.poison),
+        ];
+        let specials: u32 = kinds.iter().map(|(_, weight)| weight).sum();
+
+        if specials == 0 {
+            return None;
+        }
+
+        let mut roll = rng.gen_range(0..specials + self.nothing);
+
+        for (kind, weight) in kinds {
+            if roll < weight {
+                return Some(kind);
+            }
+            roll -= weight;
+        }
+
+        None
     }
 }
 
-impl Collidable for Point {
-    fn get_border(&self) -> Vec<Coord> {
-        vec![self.coord.clone()]
+/// A food the snake is looking for, placed randomly on the board.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Point {
+    pub cell: Cell,
+    #[serde(default)]
+    pub kind: FoodKind,
+    /// When the food disappears, in milliseconds of game time, never if
+    /// `None`.
+    #[serde(default)]
+    pub expires_ms: Option<u64>,
+}
+
+impl Point {
+    pub fn new(cell: Cell) -> Self {
+        Point {
+            cell,
+            kind: FoodKind::Normal,
+            expires_ms: None,
+        }
+    }
+
+    /// Places a point on a random cell that is neither a wall, part of the
+    /// snake nor in `taken`, and that the snake can reach. The cells of
+    /// `zone` are preferred when any of them is free. Returns `None` when
+    /// there is no such cell left.
+    pub fn spawn(
+        board: Board,
+        walls: &Walls,
+        snake: &Snake,
+        zone: &[Cell],
+        taken: &[Cell],
+        rng: &mut impl Rng,
+    ) -> Option<Self> {
+        let free: Vec<Cell> = board
+            .flood_fill(snake.head(), |cell| walls.contains(cell))
+            .into_iter()
+            .filter(|&cell| !snake.contains(cell) && !taken.contains(&cell))
+            .collect();
+
+        if free.is_empty() {
+            return None;
+        }
+
+        let zone: HashSet<Cell> = zone.iter().copied().collect();
+        let in_zone: Vec<Cell> = free
+            .iter()
+            .copied()
+            .filter(|cell| zone.contains(cell))
+            .collect();
+        let free = if in_zone.is_empty() { free } else { in_zone };
+
+        Some(Point::new(free[rng.gen_range(0..free.len())]))
     }
 }
 
 impl Shape for Point {
     fn draw(&self, painter: &mut ratatui::widgets::canvas::Painter) {
-        let line = Line {
-            x1: self.coord.x,
-            y1: self.coord.y,
-            x2: self.coord.x,
-            y2: self.coord.y,
-            color: Color::Red,
+        Painted::new(self, self.kind.color()).draw(painter);
+    }
+}
+
+impl Shape for Painted<'_, Point> {
+    fn draw(&self, painter: &mut ratatui::widgets::canvas::Painter) {
+        let cell = self.item.cell;
+
+        Points {
+            coords: &[(cell.x as f64, cell.y as f64)],
+            color: self.color,
+        }
+        .draw(painter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    #[test]
+    fn picks_nothing_without_special_food() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let untouched = rng.clone();
+
+        assert_eq!(FoodWeights::none().pick(&mut rng), None);
+        // games without special food play like before they existed
+        assert_eq!(rng, untouched);
+    }
+
+    #[test]
+    fn picks_by_weight() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let only_poison = FoodWeights {
+            nothing: 0,
+            poison: 1,
+            ..FoodWeights::none()
         };
 
-        line.draw(painter);
+        for _ in 0..20 {
+            assert_eq!(only_poison.pick(&mut rng), Some(FoodKind::Poison));
+        }
+
+        let picks: Vec<_> = (0..1000)
+            .map(|_| FoodWeights::default().pick(&mut rng))
+            .collect();
+        let nothing = picks.iter().filter(|kind| kind.is_none()).count();
+        // 10 in 16 by default
+        assert!((500..750).contains(&nothing));
+        assert!(picks.contains(&Some(FoodKind::Bonus)));
     }
 }
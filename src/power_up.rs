@@ -0,0 +1,88 @@
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::grid::Cell;
+
+/// One chance out of this many to place a power-up after eating.
+pub const POWER_UP_CHANCE: u32 = 8;
+
+/// How many characters the countdown of a running power-up is drawn with.
+pub const POWER_UP_BAR: usize = 10;
+
+/// How far from the head the food is attracted by the magnet, in cells.
+pub const MAGNET_RANGE: i32 = 8;
+
+/// A bonus lasting a few seconds once picked up.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerUp {
+    /// The snake can go through its own body.
+    Ghost,
+    /// The food within [`MAGNET_RANGE`] cells drifts toward the head.
+    Magnet,
+    /// The game runs at half speed.
+    SlowMotion,
+}
+
+impl PowerUp {
+    pub const ALL: [PowerUp; 3] = [PowerUp::Ghost, PowerUp::Magnet, PowerUp::SlowMotion];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerUp::Ghost => "Ghost",
+            PowerUp::Magnet => "Magnet",
+            PowerUp::SlowMotion => "Slow-mo",
+        }
+    }
+
+    /// How long the power-up lasts, in milliseconds of game time.
+    pub fn duration_ms(self) -> u64 {
+        match self {
+            PowerUp::Ghost => 5000,
+            PowerUp::Magnet => 8000,
+            PowerUp::SlowMotion => 6000,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            PowerUp::Ghost => Color::LightCyan,
+            PowerUp::Magnet => Color::LightMagenta,
+            PowerUp::SlowMotion => Color::LightGreen,
+        }
+    }
+
+    /// The character the power-up is drawn with on the board.
+    pub fn glyph(self) -> &'static str {
+        match self {
+            PowerUp::Ghost => "G",
+            PowerUp::Magnet => "M",
+            PowerUp::SlowMotion => "S",
+        }
+    }
+}
+
+/// A power-up waiting on the board to be picked up.
+#[derive(Serialize, Deserialize)]
+pub struct Pickup {
+    pub cell: Cell,
+    pub power_up: PowerUp,
+    /// When it disappears, in milliseconds of game time.
+    pub expires_ms: u64,
+}
+
+/// A power-up picked up and still running.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Active {
+    pub power_up: PowerUp,
+    /// When it runs out, in milliseconds of game time.
+    pub ends_ms: u64,
+}
+
+impl Active {
+    /// How much of the power-up is left at `now_ms`, from 1 when just picked
+    /// up to 0 when running out.
+    pub fn remaining(&self, now_ms: u64) -> f64 {
+        self.ends_ms.saturating_sub(now_ms) as f64 / self.power_up.duration_ms() as f64
+    }
+}
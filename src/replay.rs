@@ -0,0 +1,272 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::{Engine, Events},
+    game::{Direction, GameState, Settings},
+    grid::Board,
+    level::Level,
+    storage,
+};
+
+/// Bumped every time the format of the replay files changes.
+pub const REPLAY_VERSION: u32 = 3;
+
+/// Everything needed to play a game again: the engine is deterministic, so
+/// the seed, the settings and the turn taken on every tick are enough.
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub board: Board,
+    pub settings: Settings,
+    /// The level the game was played on, if any.
+    #[serde(default)]
+    pub level: Option<Level>,
+    /// The turn taken on every tick, `None` when the snake went straight.
+    pub inputs: Vec<Option<Direction>>,
+    /// [`Engine::state_hash`] after every tick, to detect desyncs.
+    pub hashes: Vec<u64>,
+    /// The final score, handy to tell replay files apart.
+    pub score: i32,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let replay: Replay = storage::load_json(path)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such file"))?;
+
+        if replay.version != REPLAY_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "replay version {} is not supported, expected {}",
+                    replay.version, REPLAY_VERSION
+                ),
+            ));
+        }
+
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        storage::save_json(path, self)
+    }
+
+    /// Saves the replay in [`replays_dir`] under a name made of the current
+    /// time and the seed, and returns where it was written.
+    pub fn save_to_replays_dir(&self) -> io::Result<PathBuf> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        let path = replays_dir()?.join(format!("{}-{}.json", timestamp, self.seed));
+
+        self.save(&path)?;
+
+        Ok(path)
+    }
+}
+
+/// Collects the inputs of a game while it is being played.
+#[derive(Serialize, Deserialize)]
+pub struct Recorder {
+    replay: Replay,
+}
+
+impl Recorder {
+    /// Starts recording the game `engine` is about to play.
+    pub fn new(engine: &Engine) -> Self {
+        Recorder {
+            replay: Replay {
+                version: REPLAY_VERSION,
+                seed: engine.seed,
+                board: engine.board,
+                settings: engine.settings.clone(),
+                level: engine.level.clone(),
+                inputs: Vec::new(),
+                hashes: Vec::new(),
+                score: 0,
+            },
+        }
+    }
+
+    /// Records the outcome of a call to [`Engine::tick`].
+    pub fn record(&mut self, engine: &Engine, events: &Events) {
+        if events.ticked {
+            self.replay.inputs.push(events.turn);
+            self.replay.hashes.push(engine.state_hash());
+        }
+    }
+
+    pub fn finish(self, engine: &Engine) -> Replay {
+        Replay {
+            score: engine.game.score,
+            ..self.replay
+        }
+    }
+}
+
+/// Plays a [`Replay`] back by feeding its inputs to a fresh [`Engine`].
+pub struct Player {
+    replay: Replay,
+    engine: Engine,
+    /// The first tick whose state didn't match the recorded one.
+    desync: Option<usize>,
+}
+
+impl Player {
+    pub fn new(replay: Replay) -> Self {
+        let engine = Player::new_engine(&replay);
+
+        Player {
+            replay,
+            engine,
+            desync: None,
+        }
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// How many ticks have been played so far.
+    pub fn tick(&self) -> usize {
+        self.engine.ticks as usize
+    }
+
+    /// How many ticks the whole replay lasts.
+    pub fn len(&self) -> usize {
+        self.replay.inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replay.inputs.is_empty()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.tick() >= self.len() || self.engine.game.state != GameState::Running
+    }
+
+    pub fn desync(&self) -> Option<usize> {
+        self.desync
+    }
+
+    /// Plays the next tick, if there is one left.
+    pub fn step(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+
+        let tick = self.tick();
+        let input = self.replay.inputs[tick];
+
+        if let Some(direction) = input {
+            self.engine.queue_turn(direction);
+        }
+        self.engine.tick();
+
+        if self.desync.is_none() && self.replay.hashes.get(tick) != Some(&self.engine.state_hash())
+        {
+            self.desync = Some(tick);
+        }
+    }
+
+    /// Jumps to `tick`. Going back means playing the game again from the
+    /// start, as the engine can only go forward.
+    pub fn seek(&mut self, tick: usize) {
+        let tick = tick.min(self.len());
+
+        if tick < self.tick() {
+            self.engine = Player::new_engine(&self.replay);
+        }
+
+        while self.tick() < tick && !self.is_finished() {
+            self.step();
+        }
+    }
+
+    fn new_engine(replay: &Replay) -> Engine {
+        let mut engine = match &replay.level {
+            Some(level) => Engine::with_level(level.clone(), replay.settings.clone(), replay.seed),
+            None => Engine::new(replay.board, replay.settings.clone(), replay.seed),
+        };
+        engine.start();
+
+        engine
+    }
+}
+
+/// Where the replays of the finished games are written.
+pub fn replays_dir() -> io::Result<PathBuf> {
+    Ok(storage::data_dir()?.join("replays"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot;
+
+    /// A game played by the bot, recorded.
+    fn recorded_game(ticks: usize) -> Replay {
+        let mut engine = Engine::new(Board::new(30, 16), Settings::default(), 42);
+        engine.start();
+        let mut recorder = Recorder::new(&engine);
+
+        for _ in 0..ticks {
+            if let Some(direction) = bot::next_turn(&engine) {
+                engine.queue_turn(direction);
+            }
+            let events = engine.tick();
+            recorder.record(&engine, &events);
+        }
+
+        recorder.finish(&engine)
+    }
+
+    fn play_back(replay: Replay) -> Player {
+        let mut player = Player::new(replay);
+        while !player.is_finished() {
+            player.step();
+        }
+
+        player
+    }
+
+    #[test]
+    fn replays_play_out_like_the_recorded_game() {
+        let replay = recorded_game(300);
+        let score = replay.score;
+        let player = play_back(replay);
+
+        assert_eq!(player.desync(), None);
+        assert_eq!(player.engine().game.score, score);
+    }
+
+    #[test]
+    fn the_same_seed_and_turns_give_the_same_hashes() {
+        assert_eq!(recorded_game(200).hashes, recorded_game(200).hashes);
+    }
+
+    #[test]
+    fn finds_where_a_tampered_replay_diverges() {
+        let mut replay = recorded_game(300);
+        let tick = replay
+            .inputs
+            .iter()
+            .position(Option::is_some)
+            .expect("the bot turns at least once");
+        replay.inputs[tick] = None;
+
+        assert_eq!(play_back(replay).desync(), Some(tick));
+    }
+}
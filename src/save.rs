@@ -0,0 +1,124 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{engine::Engine, replay::Recorder, storage};
+
+/// Bumped every time the format of the save file changes.
+pub const SAVE_VERSION: u32 = 2;
+
+/// A game left in progress, restored exactly as it was, RNG included.
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    pub version: u32,
+    pub engine: Engine,
+    /// The replay recorded so far, so the resumed game is recorded whole.
+    pub recorder: Option<Recorder>,
+}
+
+impl SavedGame {
+    pub fn new(engine: Engine, recorder: Option<Recorder>) -> Self {
+        SavedGame {
+            version: SAVE_VERSION,
+            engine,
+            recorder,
+        }
+    }
+
+    pub fn exists() -> bool {
+        save_path().is_ok_and(|path| path.exists())
+    }
+
+    /// Loads the saved game and deletes the save file, so a game can only
+    /// be resumed once. A save of another version is left alone.
+    pub fn take() -> io::Result<Option<Self>> {
+        SavedGame::take_from(&save_path()?)
+    }
+
+    fn take_from(path: &Path) -> io::Result<Option<Self>> {
+        let Some(saved) = storage::load_json::<serde_json::Value>(path)? else {
+            return Ok(None);
+        };
+
+        // checked first, the rest of an older save may not even parse
+        let version = saved.get("version").and_then(serde_json::Value::as_u64);
+        if version != Some(SAVE_VERSION.into()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "save version {} is not supported, expected {}",
+                    version.map_or_else(|| "none".to_string(), |version| version.to_string()),
+                    SAVE_VERSION
+                ),
+            ));
+        }
+        let saved = serde_json::from_value(saved)?;
+
+        fs::remove_file(path)?;
+
+        Ok(Some(saved))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        storage::save_json(&save_path()?, self)
+    }
+}
+
+/// Where the game in progress is saved.
+pub fn save_path() -> io::Result<PathBuf> {
+    Ok(storage::data_dir()?.join("save.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bot, game::Settings, grid::Board};
+
+    fn tick(engine: &mut Engine) {
+        if let Some(direction) = bot::next_turn(engine) {
+            engine.queue_turn(direction);
+        }
+        engine.tick();
+    }
+
+    #[test]
+    fn a_restored_game_plays_on_the_same() {
+        let mut engine = Engine::new(Board::new(30, 16), Settings::default(), 11);
+        engine.start();
+        for _ in 0..40 {
+            tick(&mut engine);
+        }
+
+        let saved = SavedGame::new(engine, None);
+        let json = serde_json::to_string(&saved).unwrap();
+        let mut engine = saved.engine;
+        let mut restored = serde_json::from_str::<SavedGame>(&json).unwrap().engine;
+
+        for _ in 0..100 {
+            tick(&mut engine);
+            tick(&mut restored);
+            assert_eq!(restored.state_hash(), engine.state_hash());
+        }
+    }
+
+    #[test]
+    fn keeps_a_save_of_another_version() {
+        let path =
+            std::env::temp_dir().join(format!("ratatui-snake-save-{}.json", std::process::id()));
+        let engine = Engine::new(Board::new(30, 16), Settings::default(), 11);
+        let mut saved = SavedGame::new(engine, None);
+
+        saved.version = SAVE_VERSION + 1;
+        storage::save_json(&path, &saved).unwrap();
+        assert!(SavedGame::take_from(&path).is_err());
+        assert!(path.exists());
+
+        saved.version = SAVE_VERSION;
+        storage::save_json(&path, &saved).unwrap();
+        assert!(SavedGame::take_from(&path).unwrap().is_some());
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,109 @@
+use std::{io, path::PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{engine::Engine, game::Mode, storage};
+
+/// How many scores the table keeps.
+pub const MAX_SCORES: usize = 10;
+
+/// The longest name kept in the table, longer ones are cut.
+pub const MAX_NAME_LENGTH: usize = 16;
+
+/// A finished game that made it into the high score table.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Score {
+    pub name: String,
+    pub score: i32,
+    /// The length of the snake at the end of the game.
+    pub length: usize,
+    /// How long the game lasted, in seconds.
+    pub duration: u64,
+    pub date: DateTime<Local>,
+    pub mode: Mode,
+    pub seed: u64,
+}
+
+impl Score {
+    /// The score of the game `engine` just finished, played by `name`. The
+    /// name is trimmed and cut to [`MAX_NAME_LENGTH`] characters, an empty
+    /// one is kept as `anonymous`.
+    pub fn new(engine: &Engine, name: &str) -> Self {
+        let name = name.trim();
+        let name = if name.is_empty() {
+            "anonymous".to_string()
+        } else {
+            name.chars().take(MAX_NAME_LENGTH).collect()
+        };
+
+        Score {
+            name,
+            score: engine.game.score,
+            length: engine.snake.len(),
+            duration: engine.elapsed_ms / 1000,
+            date: Local::now(),
+            mode: engine.settings.mode,
+            seed: engine.seed,
+        }
+    }
+}
+
+/// The best [`MAX_SCORES`] scores ever made, best first.
+#[derive(Default, Serialize, Deserialize)]
+pub struct HighScores {
+    pub scores: Vec<Score>,
+}
+
+impl HighScores {
+    /// Loads the table from [`scores_path`], empty if there is none yet.
+    pub fn load() -> io::Result<Self> {
+        Ok(storage::load_json(&scores_path()?)?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        storage::save_json(&scores_path()?, self)
+    }
+
+    /// Whether `score` is good enough to enter the table.
+    pub fn is_high_score(&self, score: i32) -> bool {
+        score > 0
+            && (self.scores.len() < MAX_SCORES
+                || self.scores.last().is_some_and(|last| score > last.score))
+    }
+
+    /// Adds `score` to the table, dropping the worst one if it is full.
+    pub fn insert(&mut self, score: Score) {
+        let position = self
+            .scores
+            .iter()
+            .position(|other| score.score > other.score)
+            .unwrap_or(self.scores.len());
+
+        self.scores.insert(position, score);
+        self.scores.truncate(MAX_SCORES);
+    }
+}
+
+/// Where the high score table is stored.
+pub fn scores_path() -> io::Result<PathBuf> {
+    Ok(storage::data_dir()?.join("scores.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game::Settings, grid::Board};
+
+    #[test]
+    fn cuts_and_trims_the_names() {
+        let engine = Engine::new(Board::new(20, 10), Settings::default(), 1);
+
+        assert_eq!(
+            Score::new(&engine, " a-very-long-ssh-user-name ").name,
+            "a-very-long-ssh-"
+        );
+        assert_eq!(Score::new(&engine, "  ").name, "anonymous");
+        assert_eq!(Score::new(&engine, "\u{e9}t\u{e9}").name, "\u{e9}t\u{e9}");
+    }
+}
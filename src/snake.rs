@@ -1,141 +1,208 @@
-use collision_detection::{coord::Coord, Collidable};
+use std::collections::{HashMap, VecDeque};
+
 use ratatui::{
     style::Color,
-    widgets::canvas::{Line, Shape},
+    widgets::canvas::{Painter, Points, Shape},
 };
+use serde::{Deserialize, Serialize, Serializer};
 
-use crate::game::Direction;
+use crate::{
+    game::Direction,
+    grid::{Board, Cell},
+    ui::Painted,
+};
 
-pub struct SnakeHead {
-    pub coord: Coord,
+/// A snake stored as the cells it covers, from the head to the tail. Two
+/// cells next to each other in the list are not always next to each other on
+/// the screen: on a board that wraps around the snake can be split across the
+/// edges.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "StoredSnake")]
+pub struct Snake {
     pub direction: Direction,
+    cells: VecDeque<Cell>,
+    /// How many times each cell in `cells` except the head is covered, to
+    /// check bites in O(1).
+    #[serde(serialize_with = "serialize_body")]
+    body: HashMap<Cell, u32>,
+    /// How many ticks the tail still has to stay still for.
+    growth: usize,
 }
 
-impl SnakeHead {
-    pub fn new() -> Self {
-        Self {
-            coord: Coord::new(12.0, 0.0),
-            direction: Direction::Right,
+/// A snake as saved, its body is counted again from the cells.
+#[derive(Deserialize)]
+struct StoredSnake {
+    direction: Direction,
+    cells: VecDeque<Cell>,
+    growth: usize,
+}
+
+impl From<StoredSnake> for Snake {
+    fn from(stored: StoredSnake) -> Self {
+        Snake {
+            direction: stored.direction,
+            body: count_body(&stored.cells),
+            cells: stored.cells,
+            growth: stored.growth,
         }
     }
+}
 
-    pub fn change_direction(&mut self, direction: Direction) {
-        self.direction = direction;
-    }
+/// Writes the body as the list of cells it covers, like it always was.
+fn serialize_body<S: Serializer>(
+    body: &HashMap<Cell, u32>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(body.keys())
+}
 
-    pub fn move_head(&mut self) {
-        match self.direction {
-            Direction::Up => self.coord.move_up(1.0),
-            Direction::Down => self.coord.move_down(1.0),
-            Direction::Left => self.coord.move_left(1.0),
-            Direction::Right => self.coord.move_right(1.0),
-        }
+fn count_body(cells: &VecDeque<Cell>) -> HashMap<Cell, u32> {
+    let mut body = HashMap::new();
+    for &cell in cells.iter().skip(1) {
+        *body.entry(cell).or_default() += 1;
     }
+
+    body
 }
 
-impl Collidable for SnakeHead {
-    fn get_border(&self) -> Vec<Coord> {
-        vec![self.coord.clone()]
+impl Snake {
+    /// Creates a snake of `length` cells with its head on `head`, laid out
+    /// straight behind it.
+    pub fn new(head: Cell, direction: Direction, length: usize) -> Self {
+        let mut cells = VecDeque::with_capacity(length);
+        let mut cell = head;
+
+        for _ in 0..length.max(1) {
+            cells.push_back(cell);
+            cell = cell.neighbour(direction.opposite());
+        }
+
+        Snake {
+            direction,
+            body: count_body(&cells),
+            cells,
+            growth: 0,
+        }
     }
-}
 
-pub struct SnakeBody {
-    corners: Vec<Coord>,
-}
+    /// A snake covering `cells`, from the head to the tail, e.g. received
+    /// from a server.
+    pub fn from_cells(cells: Vec<Cell>, direction: Direction) -> Self {
+        let cells = VecDeque::from(cells);
 
-impl SnakeBody {
-    fn new() -> Self {
-        SnakeBody {
-            corners: vec![Coord { x: 0.0, y: 0.0 }],
+        Snake {
+            direction,
+            body: count_body(&cells),
+            cells,
+            growth: 0,
         }
     }
 
-    fn move_tail(&mut self, head_coord: &Coord, total_length: f64) {
-        let tail_coord = &self.corners[0];
+    pub fn head(&self) -> Cell {
+        self.cells[0]
+    }
 
-        if self.corners.len() == 1 {
-            let tail_distance_x = Coord::get_x_distance(tail_coord, head_coord);
-            let tail_distance_y = Coord::get_y_distance(tail_coord, head_coord);
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
 
-            if tail_distance_x + tail_distance_y > total_length {
-                self.corners[0].move_toward(head_coord);
-            }
-        } else {
-            let next_corner = self.corners[1].clone();
-            self.corners[0].move_toward(&next_corner);
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
 
-            if self.corners[0].compare(&next_corner) {
-                self.corners.remove(0);
-            }
-        }
+    /// The cells of the snake, from the head to the tail.
+    pub fn cells(&self) -> impl Iterator<Item = &Cell> {
+        self.cells.iter()
     }
-}
 
-impl Collidable for SnakeBody {
-    fn get_border(&self) -> Vec<Coord> {
-        self.corners.clone()
+    /// Whether any part of the snake, head included, is on `cell`.
+    pub fn contains(&self, cell: Cell) -> bool {
+        self.head() == cell || self.body.contains_key(&cell)
     }
-}
 
-pub struct Snake {
-    pub total_length: i32,
-    pub body: SnakeBody,
-    pub head: SnakeHead,
-}
+    /// Whether the head ended up on the rest of the body.
+    pub fn bites_itself(&self) -> bool {
+        self.body.contains_key(&self.head())
+    }
 
-impl Snake {
-    pub fn new() -> Self {
-        Snake {
-            total_length: 12,
-            body: SnakeBody::new(),
-            head: SnakeHead::new(),
+    /// Takes up to `cells` cells from the tail, the head always stays.
+    pub fn shrink(&mut self, cells: usize) {
+        for _ in 0..cells {
+            if self.cells.len() <= 1 {
+                break;
+            }
+            self.drop_tail();
         }
     }
 
+    /// Makes the snake one cell longer over the next move.
     pub fn grow(&mut self) {
-        self.total_length += 1;
+        self.growth += 1;
+    }
+
+    /// Moves the head one cell forward on `board`, the tail following
+    /// unless the snake is growing.
+    pub fn move_snake(&mut self, board: &Board) {
+        let head = self.head();
+        let next = board.neighbour(head, self.direction);
+
+        if self.growth > 0 {
+            self.growth -= 1;
+        } else {
+            self.drop_tail();
+        }
+
+        if !self.cells.is_empty() {
+            *self.body.entry(head).or_default() += 1;
+        }
+        self.cells.push_front(next);
     }
 
-    pub fn move_snake(&mut self) {
-        self.head.move_head();
-        self.body
-            .move_tail(&self.head.coord, self.total_length as f64);
+    /// Removes the last cell. A snake going through itself covers some
+    /// cells twice, these stay in the body until the last of them leaves.
+    fn drop_tail(&mut self) {
+        // the tail of a single cell snake is its head, not in the body
+        if self.cells.len() < 2 {
+            self.cells.pop_back();
+            return;
+        }
+
+        if let Some(tail) = self.cells.pop_back() {
+            if let Some(count) = self.body.get_mut(&tail) {
+                *count -= 1;
+                if *count == 0 {
+                    self.body.remove(&tail);
+                }
+            }
+        }
     }
 
+    /// Points the head toward `direction`, the snake moves on the next
+    /// call to [`Snake::move_snake`].
     pub fn change_direction(&mut self, direction: Direction) {
-        self.head.change_direction(direction);
-        self.body
-            .corners
-            .push(Coord::new(self.head.coord.x, self.head.coord.y));
-        self.move_snake();
+        self.direction = direction;
     }
 }
 
 impl Shape for Snake {
-    fn draw(&self, painter: &mut ratatui::widgets::canvas::Painter) {
-        self.body.corners.windows(2).for_each(|arr| {
-            let start_coord = &arr[0];
-            let end_coord = &arr[1];
-
-            let body_line = Line {
-                x1: start_coord.x,
-                y1: start_coord.y,
-                x2: end_coord.x,
-                y2: end_coord.y,
-                color: Color::Blue,
-            };
-
-            body_line.draw(painter);
-        });
-
-        let head_line = Line {
-            x1: self.head.coord.x,
-            y1: self.head.coord.y,
-            x2: self.body.corners.last().unwrap().x,
-            y2: self.body.corners.last().unwrap().y,
-            color: Color::Blue,
-        };
-
-        head_line.draw(painter);
+    fn draw(&self, painter: &mut Painter) {
+        Painted::new(self, Color::Blue).draw(painter);
+    }
+}
+
+impl Shape for Painted<'_, Snake> {
+    fn draw(&self, painter: &mut Painter) {
+        let coords: Vec<(f64, f64)> = self
+            .item
+            .cells
+            .iter()
+            .map(|cell| (cell.x as f64, cell.y as f64))
+            .collect();
+
+        Points {
+            coords: &coords,
+            color: self.color,
+        }
+        .draw(painter);
     }
 }
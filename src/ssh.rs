@@ -0,0 +1,718 @@
+//! Games played over SSH. The server embeds its own SSH server: every
+//! session asking for a terminal gets a game of its own, drawn in that
+//! terminal at its size. Sessions never get a shell, only the game, and
+//! quitting it closes the connection.
+//!
+//! Anyone reaching the server can play, without a password, as long as
+//! fewer than its maximum of sessions are playing: the SSH user name is the
+//! name kept in the high scores. Sessions don't save their
+//! games, replays or keys, and the high scores are shared by the sessions
+//! of the server, which is the only one to write them.
+
+use std::{
+    cell::Cell,
+    io::{self, Write},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crossterm::{
+    cursor,
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+    QueueableCommand,
+};
+use ratatui::{
+    backend::{Backend, CrosstermBackend, WindowSize},
+    buffer,
+    layout::{Rect, Size},
+    Terminal,
+};
+use russh::{
+    keys::{ssh_key::LineEnding, Algorithm, PrivateKey},
+    server::{Auth, Config, Handler, Msg, Server, Session},
+    Channel, ChannelId, CryptoVec, MethodKind, MethodSet,
+};
+use tokio::sync::mpsc as async_mpsc;
+
+use crate::{
+    app::{self, EventSource, PlayMode},
+    config::Config as AppConfig,
+    engine::Engine,
+    grid::Board,
+    scores::HighScores,
+    storage,
+};
+
+/// The port used when the address doesn't say.
+pub const DEFAULT_PORT: u16 = 2222;
+
+/// How many sessions can play at once unless set otherwise.
+pub const MAX_SESSIONS: usize = 16;
+
+/// How many frames can wait to be sent to a session before its game waits
+/// for them to go.
+const PENDING_FRAMES: usize = 4;
+
+/// Creates the game of a session, on a board the size of its terminal
+/// unless set otherwise. The error is shown to the player.
+pub type NewEngine = dyn Fn(Board) -> Result<Engine, String> + Send + Sync;
+
+/// Where the host key of the server is kept.
+pub fn host_key_path() -> io::Result<PathBuf> {
+    Ok(storage::data_dir()?.join("ssh_host_ed25519_key"))
+}
+
+/// Reads the host key of the server at `path`, generated there on the
+/// first run so that players see the same key every time.
+pub fn host_key(path: &Path) -> io::Result<PrivateKey> {
+    if path.exists() {
+        return russh::keys::load_secret_key(path, None).map_err(io::Error::other);
+    }
+
+    let key =
+        PrivateKey::random(&mut rand::rngs::OsRng, Algorithm::Ed25519).map_err(io::Error::other)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    key.write_openssh_file(path, LineEnding::LF)
+        .map_err(io::Error::other)?;
+
+    Ok(key)
+}
+
+/// Everything the sessions of a server share.
+struct Shared {
+    new_engine: Box<NewEngine>,
+    config: AppConfig,
+    high_scores: Mutex<HighScores>,
+    log: Box<dyn Fn(String) + Send + Sync>,
+    /// How many sessions are playing, at most `max_sessions`.
+    sessions: AtomicUsize,
+    max_sessions: usize,
+}
+
+/// Hosts a game for every SSH session connecting to it.
+pub struct SshServer {
+    shared: Arc<Shared>,
+}
+
+impl SshServer {
+    /// A server starting the games of its sessions with `new_engine`,
+    /// drawn and played as `config` says, telling `log` who comes and goes.
+    /// Clients past the `max_sessions` playing already are turned away.
+    pub fn new(
+        new_engine: Box<NewEngine>,
+        config: AppConfig,
+        max_sessions: usize,
+        log: impl Fn(String) + Send + Sync + 'static,
+    ) -> Self {
+        // an unreadable table shouldn't prevent playing, it starts over empty
+        let high_scores = HighScores::load().unwrap_or_default();
+
+        SshServer {
+            shared: Arc::new(Shared {
+                new_engine,
+                config,
+                high_scores: Mutex::new(high_scores),
+                log: Box::new(log),
+                sessions: AtomicUsize::new(0),
+                max_sessions,
+            }),
+        }
+    }
+
+    /// Accepts sessions on `addr` with the host key `key`, forever.
+    pub fn run(mut self, addr: &str, key: PrivateKey) -> io::Result<()> {
+        let config = Arc::new(Config {
+            keys: vec![key],
+            methods: MethodSet::from(&[MethodKind::None][..]),
+            nodelay: true,
+            ..Config::default()
+        });
+        let runtime = tokio::runtime::Runtime::new()?;
+
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            (self.shared.log)(format!("Waiting for players on {}", listener.local_addr()?));
+
+            self.run_on_socket(config, &listener).await
+        })
+    }
+}
+
+impl Server for SshServer {
+    type Handler = Client;
+
+    fn new_client(&mut self, peer: Option<SocketAddr>) -> Client {
+        Client {
+            shared: self.shared.clone(),
+            peer: peer.map_or_else(|| "somewhere".to_string(), |peer| peer.to_string()),
+            user: String::new(),
+            counted: false,
+            channel: None,
+            size: (80, 24),
+            inputs: None,
+        }
+    }
+}
+
+/// A client connected over SSH, playing a single game.
+pub struct Client {
+    shared: Arc<Shared>,
+    peer: String,
+    user: String,
+    /// Whether the client is one of the sessions playing.
+    counted: bool,
+    /// The session channel the game is played on.
+    channel: Option<ChannelId>,
+    /// The columns and rows of the terminal of the client.
+    size: (u16, u16),
+    /// Where the keys go once the game started.
+    inputs: Option<mpsc::Sender<Event>>,
+}
+
+impl Client {
+    /// Starts the game of the session on `channel`, on its own thread.
+    fn start(&mut self, channel: ChannelId, session: &mut Session) {
+        let (inputs, received) = mpsc::channel();
+        let (frames, mut to_send) = async_mpsc::channel::<Vec<u8>>(PENDING_FRAMES);
+        let handle = session.handle();
+
+        // the channel closes once the game is over and dropped its output
+        tokio::spawn(async move {
+            while let Some(frame) = to_send.recv().await {
+                if handle.data(channel, CryptoVec::from(frame)).await.is_err() {
+                    return;
+                }
+            }
+            let _ = handle.exit_status_request(channel, 0).await;
+            let _ = handle.eof(channel).await;
+            let _ = handle.close(channel).await;
+        });
+
+        let shared = self.shared.clone();
+        let user = self.user.clone();
+        let size = self.size;
+        thread::spawn(move || {
+            let output = Output {
+                buffer: Vec::new(),
+                frames,
+            };
+            match play(&shared, &user, size, received, output) {
+                Err(error) if error.kind() != io::ErrorKind::ConnectionAborted => {
+                    (shared.log)(format!("The game of {user} stopped: {error}"));
+                }
+                _ => {}
+            }
+        });
+
+        self.inputs = Some(inputs);
+        (self.shared.log)(format!("{} started a game from {}", self.user, self.peer));
+    }
+
+    /// Counts the client among the sessions playing, unless there are
+    /// enough of them already.
+    fn take_seat(&mut self) -> bool {
+        let max = self.shared.max_sessions;
+
+        self.counted = self
+            .shared
+            .sessions
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |sessions| {
+                (sessions < max).then_some(sessions + 1)
+            })
+            .is_ok();
+
+        self.counted
+    }
+
+    fn send(&mut self, input: Event) {
+        if let Some(inputs) = &self.inputs {
+            // the game is over when it can't receive anymore
+            if inputs.send(input).is_err() {
+                self.inputs = None;
+            }
+        }
+    }
+}
+
+impl Handler for Client {
+    type Error = russh::Error;
+
+    async fn auth_none(&mut self, user: &str) -> Result<Auth, Self::Error> {
+        self.user = user.to_string();
+        Ok(Auth::Accept)
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        // one game per connection
+        if self.channel.is_some() {
+            return Ok(false);
+        }
+        self.channel = Some(channel.id());
+
+        Ok(true)
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        _term: &str,
+        columns: u32,
+        rows: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _modes: &[(russh::Pty, u32)],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.size = (clamp_size(columns), clamp_size(rows));
+        session.channel_success(channel)
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if self.channel != Some(channel) || self.inputs.is_some() {
+            return session.channel_failure(channel);
+        }
+
+        session.channel_success(channel)?;
+
+        if !self.take_seat() {
+            let max = self.shared.max_sessions;
+            (self.shared.log)(format!(
+                "Turned {} away from {}, {max} sessions are playing already",
+                self.user, self.peer
+            ));
+
+            let message = format!("The server is full, {max} sessions are playing already\r\n");
+            session.data(channel, CryptoVec::from(message))?;
+            session.exit_status_request(channel, 1)?;
+            session.eof(channel)?;
+            return session.close(channel);
+        }
+        self.start(channel, session);
+
+        Ok(())
+    }
+
+    /// Whatever the command asked for, the game is played instead.
+    async fn exec_request(
+        &mut self,
+        channel: ChannelId,
+        _command: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.shell_request(channel, session).await
+    }
+
+    async fn window_change_request(
+        &mut self,
+        _channel: ChannelId,
+        columns: u32,
+        rows: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.size = (clamp_size(columns), clamp_size(rows));
+        self.send(Event::Resize(self.size.0, self.size.1));
+
+        Ok(())
+    }
+
+    async fn data(
+        &mut self,
+        _channel: ChannelId,
+        data: &[u8],
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        for key in decode_keys(data) {
+            self.send(Event::Key(key));
+        }
+
+        Ok(())
+    }
+
+    async fn channel_eof(
+        &mut self,
+        _channel: ChannelId,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.inputs = None;
+        Ok(())
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        if self.counted {
+            self.shared.sessions.fetch_sub(1, Ordering::SeqCst);
+        }
+        if self.channel.is_some() {
+            (self.shared.log)(format!("{} left", self.user));
+        }
+    }
+}
+
+fn clamp_size(cells: u32) -> u16 {
+    cells.clamp(1, u16::MAX as u32) as u16
+}
+
+/// The terminal of a session: what is drawn is sent to its channel on every
+/// flush.
+struct Output {
+    buffer: Vec<u8>,
+    frames: async_mpsc::Sender<Vec<u8>>,
+}
+
+impl Clone for Output {
+    fn clone(&self) -> Self {
+        Output {
+            buffer: Vec::new(),
+            frames: self.frames.clone(),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        // waits for the client to catch up rather than piling frames up
+        self.frames
+            .blocking_send(std::mem::take(&mut self.buffer))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "the session closed"))
+    }
+}
+
+/// The screen of a session, as large as the terminal of the client says.
+struct SessionBackend {
+    backend: CrosstermBackend<Output>,
+    size: Rc<Cell<(u16, u16)>>,
+}
+
+impl Backend for SessionBackend {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a buffer::Cell)>,
+    {
+        self.backend.draw(content)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.backend.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.backend.show_cursor()
+    }
+
+    /// Asking would read the terminal of the server, the game never needs
+    /// to.
+    fn get_cursor(&mut self) -> io::Result<(u16, u16)> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the cursor of a session can't be read",
+        ))
+    }
+
+    fn set_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.backend.set_cursor(x, y)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.backend.clear()
+    }
+
+    fn size(&self) -> io::Result<Rect> {
+        let (columns, rows) = self.size.get();
+
+        Ok(Rect::new(0, 0, columns, rows))
+    }
+
+    fn window_size(&mut self) -> io::Result<WindowSize> {
+        let (width, height) = self.size.get();
+
+        Ok(WindowSize {
+            columns_rows: Size { width, height },
+            pixels: Size::default(),
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Backend::flush(&mut self.backend)
+    }
+}
+
+/// The keys and resizes a session receives, resizing its
+/// [`SessionBackend`] before the game hears of them.
+struct SessionInput {
+    inputs: mpsc::Receiver<Event>,
+    size: Rc<Cell<(u16, u16)>>,
+}
+
+impl SessionInput {
+    fn received(&self, event: Event) -> Event {
+        if let Event::Resize(columns, rows) = event {
+            self.size.set((columns, rows));
+        }
+
+        event
+    }
+}
+
+impl EventSource for SessionInput {
+    fn poll(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        match self.inputs.recv_timeout(timeout) {
+            Ok(event) => Ok(Some(self.received(event))),
+            Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(left()),
+        }
+    }
+
+    fn read(&mut self) -> io::Result<Event> {
+        let event = self.inputs.recv().map_err(|_| left())?;
+
+        Ok(self.received(event))
+    }
+}
+
+/// The error ending the game of a session whose client left.
+fn left() -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionAborted, "the client left")
+}
+
+/// Plays games for `user` until they quit or leave, like a local game
+/// that saves nothing but the high scores.
+fn play(
+    shared: &Shared,
+    user: &str,
+    size: (u16, u16),
+    inputs: mpsc::Receiver<Event>,
+    mut output: Output,
+) -> io::Result<()> {
+    output
+        .queue(EnterAlternateScreen)?
+        .queue(cursor::Hide)?
+        .flush()?;
+
+    let (cells_per_column, cells_per_row) = shared.config.theme().cells_per_char();
+    let board = Board::new(
+        i32::from(size.0) * i32::from(cells_per_column),
+        i32::from(size.1) * i32::from(cells_per_row),
+    );
+    let engine = match (shared.new_engine)(board) {
+        Ok(engine) => engine,
+        Err(message) => {
+            output
+                .queue(LeaveAlternateScreen)?
+                .write_all(format!("{message}\r\n").as_bytes())?;
+            return output.flush();
+        }
+    };
+
+    let size = Rc::new(Cell::new(size));
+    let mut terminal = Terminal::new(SessionBackend {
+        backend: CrosstermBackend::new(output.clone()),
+        size: size.clone(),
+    })?;
+    terminal.clear()?;
+
+    let mode = PlayMode::Remote {
+        user,
+        high_scores: &shared.high_scores,
+    };
+    app::play(
+        &mut terminal,
+        &mut SessionInput { inputs, size },
+        &shared.config,
+        None,
+        engine,
+        mode,
+    )?;
+
+    drop(terminal);
+    output
+        .queue(LeaveAlternateScreen)?
+        .queue(cursor::Show)?
+        .flush()
+}
+
+/// The keys typed in a terminal, sent as the bytes `data`. Unknown escape
+/// sequences are skipped.
+fn decode_keys(data: &[u8]) -> Vec<KeyEvent> {
+    let mut keys = Vec::new();
+    let mut rest = data;
+
+    while !rest.is_empty() {
+        let (key, length) = decode_key(rest);
+
+        keys.extend(key);
+        rest = &rest[length.max(1)..];
+    }
+
+    keys
+}
+
+/// The first key of `data` if it is known, and how many bytes it took.
+fn decode_key(data: &[u8]) -> (Option<KeyEvent>, usize) {
+    let plain = |code| Some(KeyEvent::new(code, KeyModifiers::NONE));
+
+    match data[0] {
+        0x1b => match data.get(1) {
+            None | Some(0x1b) => (plain(KeyCode::Esc), 1),
+            Some(b'[') => decode_csi(&data[2..])
+                .map_or((None, data.len()), |(key, length)| (Some(key), length + 2)),
+            Some(b'O') => {
+                let code = data.get(2).and_then(|&byte| final_code(byte));
+                (code.map(|code| KeyEvent::new(code, KeyModifiers::NONE)), 3)
+            }
+            // alt+key is sent as escape then the key
+            Some(_) => {
+                let (key, length) = decode_key(&data[1..]);
+                let key = key.map(|key| KeyEvent::new(key.code, key.modifiers | KeyModifiers::ALT));
+
+                (key, length + 1)
+            }
+        },
+        b'\r' | b'\n' => (plain(KeyCode::Enter), 1),
+        b'\t' => (plain(KeyCode::Tab), 1),
+        0x7f | 0x08 => (plain(KeyCode::Backspace), 1),
+        byte @ 0x01..=0x1a => {
+            let letter = (b'a' + byte - 1) as char;
+            (
+                Some(KeyEvent::new(KeyCode::Char(letter), KeyModifiers::CONTROL)),
+                1,
+            )
+        }
+        byte if byte < 0x20 => (None, 1),
+        byte => {
+            let length = match byte {
+                0xf0.. => 4,
+                0xe0.. => 3,
+                0xc0.. => 2,
+                _ => 1,
+            };
+            let c = data
+                .get(..length)
+                .and_then(|bytes| std::str::from_utf8(bytes).ok())
+                .and_then(|text| text.chars().next());
+
+            (c.and_then(|c| plain(KeyCode::Char(c))), length)
+        }
+    }
+}
+
+/// The key sent as `ESC [` then `data`, like `ESC [ A` for up or
+/// `ESC [ 1 ; 5 C` for ctrl+right, and how many bytes of `data` it took.
+fn decode_csi(data: &[u8]) -> Option<(KeyEvent, usize)> {
+    let end = data.iter().position(|byte| (0x40..=0x7e).contains(byte))?;
+    let params = std::str::from_utf8(&data[..end]).ok()?;
+    let mut params = params.split(';').map(|param| param.parse::<u8>().ok());
+    let number = params.next().flatten();
+    // the modifiers are sent as 1 plus the bits of shift, alt and ctrl
+    let modifiers = params.next().flatten().unwrap_or(1).saturating_sub(1);
+
+    let code = match data[end] {
+        b'~' => match number? {
+            1 | 7 => KeyCode::Home,
+            2 => KeyCode::Insert,
+            3 => KeyCode::Delete,
+            4 | 8 => KeyCode::End,
+            5 => KeyCode::PageUp,
+            6 => KeyCode::PageDown,
+            n @ 11..=15 => KeyCode::F(n - 10),
+            n @ 17..=21 => KeyCode::F(n - 11),
+            n @ 23..=24 => KeyCode::F(n - 12),
+            _ => return None,
+        },
+        b'Z' => KeyCode::BackTab,
+        byte => final_code(byte)?,
+    };
+    let modifiers = [
+        (1, KeyModifiers::SHIFT),
+        (2, KeyModifiers::ALT),
+        (4, KeyModifiers::CONTROL),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| modifiers & bit != 0)
+    .fold(KeyModifiers::NONE, |all, (_, modifier)| all | modifier);
+
+    Some((KeyEvent::new(code, modifiers), end + 1))
+}
+
+/// The key of the last byte of the sequences like `ESC [ A` or `ESC O P`.
+fn final_code(byte: u8) -> Option<KeyCode> {
+    match byte {
+        b'A' => Some(KeyCode::Up),
+        b'B' => Some(KeyCode::Down),
+        b'C' => Some(KeyCode::Right),
+        b'D' => Some(KeyCode::Left),
+        b'H' => Some(KeyCode::Home),
+        b'F' => Some(KeyCode::End),
+        b'P'..=b'S' => Some(KeyCode::F(byte - b'P' + 1)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn decodes_the_keys_typed_in_a_terminal() {
+        let typed = "q\u{e9}\r\x1b[A\x1bOB\x1b[1;5C\x1b[15~\x1b[3~\x03\x1bx\x7f".as_bytes();
+
+        assert_eq!(
+            decode_keys(typed),
+            [
+                key(KeyCode::Char('q'), KeyModifiers::NONE),
+                key(KeyCode::Char('\u{e9}'), KeyModifiers::NONE),
+                key(KeyCode::Enter, KeyModifiers::NONE),
+                key(KeyCode::Up, KeyModifiers::NONE),
+                key(KeyCode::Down, KeyModifiers::NONE),
+                key(KeyCode::Right, KeyModifiers::CONTROL),
+                key(KeyCode::F(5), KeyModifiers::NONE),
+                key(KeyCode::Delete, KeyModifiers::NONE),
+                key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+                key(KeyCode::Char('x'), KeyModifiers::ALT),
+                key(KeyCode::Backspace, KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_lone_escape_is_the_escape_key() {
+        assert_eq!(
+            decode_keys(b"\x1b"),
+            [key(KeyCode::Esc, KeyModifiers::NONE)]
+        );
+        assert!(decode_keys(b"\x1b[99~").is_empty());
+    }
+}
@@ -0,0 +1,31 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Where the game keeps its files, inside the XDG data directory.
+pub fn data_dir() -> io::Result<PathBuf> {
+    dirs::data_dir()
+        .map(|dir| dir.join("ratatui-snake"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory available"))
+}
+
+/// Reads a JSON file, `None` if it doesn't exist yet.
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> io::Result<Option<T>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+/// Writes `value` as JSON, creating the parent directories if needed.
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(path, serde_json::to_string(value)?)
+}
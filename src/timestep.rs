@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+/// Never run more than this many ticks to catch up, e.g. after the
+/// process was suspended, so the game doesn't fast forward.
+const MAX_CATCH_UP_TICKS: u32 = 5;
+
+/// Accumulates the real time elapsed and turns it into a fixed number of
+/// simulation ticks, so the game speed doesn't depend on how often the
+/// caller polls it.
+pub struct FixedTimestep {
+    step: Duration,
+    lag: Duration,
+    last: Instant,
+}
+
+impl FixedTimestep {
+    pub fn new(ticks_per_second: u32) -> Self {
+        FixedTimestep {
+            step: FixedTimestep::step_for(ticks_per_second),
+            lag: Duration::ZERO,
+            last: Instant::now(),
+        }
+    }
+
+    pub fn set_ticks_per_second(&mut self, ticks_per_second: u32) {
+        self.step = FixedTimestep::step_for(ticks_per_second);
+    }
+
+    /// Sets the time between two ticks directly, for rates that are not a
+    /// whole number of ticks per second.
+    pub fn set_step(&mut self, step: Duration) {
+        self.step = step;
+    }
+
+    /// How many ticks are due since the last call.
+    pub fn ticks(&mut self) -> u32 {
+        let now = Instant::now();
+        self.lag += now - self.last;
+        self.last = now;
+
+        let mut ticks = 0;
+        while self.lag >= self.step {
+            self.lag -= self.step;
+            ticks += 1;
+        }
+
+        if ticks > MAX_CATCH_UP_TICKS {
+            self.lag = Duration::ZERO;
+            ticks = MAX_CATCH_UP_TICKS;
+        }
+
+        ticks
+    }
+
+    /// How long until the next tick is due.
+    pub fn until_next_tick(&self) -> Duration {
+        self.step
+            .saturating_sub(self.lag)
+            .saturating_sub(self.last.elapsed())
+    }
+
+    fn step_for(ticks_per_second: u32) -> Duration {
+        Duration::from_secs(1) / ticks_per_second.max(1)
+    }
+}
@@ -0,0 +1,700 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols::Marker,
+    text::{Line, Span},
+    widgets::{
+        canvas::{Canvas, Points},
+        Block, Borders, Clear, Paragraph, Row, Table,
+    },
+    Frame,
+};
+
+use crate::{
+    arena::{Arena, ArenaState, MAX_PLAYERS},
+    campaign::{Progress, Stage},
+    editor::Editor,
+    engine::Engine,
+    game::GameState,
+    grid::{Board, Cell},
+    keys::{Action, KeyBindings, Remapper},
+    letters::Word,
+    power_up::{PowerUp, POWER_UP_BAR},
+    scores::HighScores,
+    snake::Snake,
+    walls::Walls,
+};
+
+/// The colours and the canvas marker the game is drawn with.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Theme {
+    pub snake: Color,
+    pub food: Color,
+    pub walls: Color,
+    pub marker: Marker,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            snake: Color::Blue,
+            food: Color::Red,
+            walls: Color::White,
+            marker: Marker::HalfBlock,
+        }
+    }
+}
+
+impl Theme {
+    /// How many cells of the board fit in a character of the terminal,
+    /// horizontally and vertically, with the marker of the theme.
+    pub fn cells_per_char(&self) -> (u16, u16) {
+        match self.marker {
+            Marker::Braille => (2, 4),
+            Marker::HalfBlock => (1, 2),
+            Marker::Dot | Marker::Block | Marker::Bar => (1, 1),
+        }
+    }
+}
+
+/// Where the cursor is drawn in the editor.
+const CURSOR_COLOR: Color = Color::Yellow;
+
+/// Where the food can spawn is drawn dimmer than the food itself.
+const FOOD_ZONE_COLOR: Color = Color::DarkGray;
+
+/// The colours of the snakes in a match, by player.
+pub const PLAYER_COLORS: [Color; MAX_PLAYERS] = [
+    Color::Blue,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Cyan,
+    Color::LightRed,
+    Color::White,
+    Color::LightBlue,
+];
+
+/// Draws a shape of the game with the given colour instead of its default
+/// one.
+pub struct Painted<'a, T> {
+    pub item: &'a T,
+    pub color: Color,
+}
+
+impl<'a, T> Painted<'a, T> {
+    pub fn new(item: &'a T, color: Color) -> Self {
+        Painted { item, color }
+    }
+}
+
+/// Renders the current state of `engine` on the whole frame.
+pub fn draw(frame: &mut Frame, engine: &Engine, theme: &Theme) {
+    let area = frame.size();
+    let game = &engine.game;
+    let right = (engine.board.width - 1) as f64;
+    let top = (engine.board.height - 1) as f64;
+    // texts are laid out around the center of the board
+    let center = engine.board.center();
+    let (cx, cy) = (center.x as f64, center.y as f64);
+
+    frame.render_widget(
+        Canvas::default()
+            .x_bounds([0.0, right])
+            .y_bounds([0.0, top])
+            .marker(theme.marker)
+            .paint(|ctx| {
+                ctx.draw(&Painted::new(&engine.walls, theme.walls));
+
+                ctx.layer();
+
+                if game.state != GameState::Startup {
+                    ctx.print(
+                        3.0,
+                        top - 4.0,
+                        format!(
+                            "Score: {}   Level: {}   Speed: {}",
+                            game.score,
+                            game.level(),
+                            game.speed()
+                        ),
+                    );
+
+                    for (i, active) in engine.active.iter().enumerate() {
+                        ctx.print(
+                            right - 24.0,
+                            top - 4.0 - 4.0 * i as f64,
+                            Span::styled(
+                                countdown(active.power_up, active.remaining(engine.elapsed_ms)),
+                                Style::default().fg(active.power_up.color()),
+                            ),
+                        );
+                    }
+                }
+
+                ctx.layer();
+
+                match game.state {
+                    GameState::Running | GameState::Paused => {
+                        let snake = if engine.has_power_up(PowerUp::Ghost) {
+                            PowerUp::Ghost.color()
+                        } else {
+                            theme.snake
+                        };
+                        ctx.draw(&Painted::new(&engine.snake, snake));
+                        if let Some(point) = &engine.point {
+                            ctx.draw(&Painted::new(point, theme.food));
+                        }
+                        if let Some(special) = &engine.special {
+                            ctx.print(
+                                special.cell.x as f64,
+                                special.cell.y as f64,
+                                Span::styled(
+                                    special.kind.glyph(),
+                                    Style::default().fg(special.kind.color()),
+                                ),
+                            );
+                        }
+                        if let Some(pickup) = &engine.power_up {
+                            ctx.print(
+                                pickup.cell.x as f64,
+                                pickup.cell.y as f64,
+                                Span::styled(
+                                    pickup.power_up.glyph(),
+                                    Style::default()
+                                        .fg(pickup.power_up.color())
+                                        .add_modifier(Modifier::BOLD),
+                                ),
+                            );
+                        }
+
+                        if game.state == GameState::Paused {
+                            ctx.print(cx - 13.0, top - 8.0, "Paused, press P to resume");
+                        }
+                    }
+                    GameState::GameOver => {
+                        ctx.draw(&Word::new("gameover".to_string(), cx - 27.0, cy));
+                        ctx.print(cx - 9.0, cy - 5.0, "Press R to restart");
+                        ctx.print(cx - 9.0, cy - 9.0, format!("Seed: {}", engine.seed));
+                    }
+                    GameState::Won => {
+                        ctx.draw(&Word::new("you win".to_string(), cx - 24.0, cy));
+                        ctx.print(cx - 9.0, cy - 5.0, "Press R to restart");
+                        ctx.print(cx - 9.0, cy - 9.0, format!("Seed: {}", engine.seed));
+                    }
+                    GameState::Startup => {
+                        ctx.draw(&Word::new("ratatui snake".to_string(), cx - 48.0, cy));
+                        ctx.print(cx - 15.0, cy - 5.0, "Press any character to start");
+                    }
+                }
+            }),
+        area,
+    )
+}
+
+/// Renders the current state of the match on `arena` on the whole frame,
+/// what to press being left to the status line.
+pub fn draw_arena(frame: &mut Frame, arena: &Arena, theme: &Theme) {
+    render_arena(frame, frame.size(), arena, theme, None);
+}
+
+/// Renders the match on `arena` in `area`. The snake of `followed` is
+/// labelled, and the scores are left to a side panel when following one.
+fn render_arena(
+    frame: &mut Frame,
+    area: Rect,
+    arena: &Arena,
+    theme: &Theme,
+    followed: Option<usize>,
+) {
+    let right = (arena.board.width - 1) as f64;
+    let top = (arena.board.height - 1) as f64;
+    let center = arena.board.center();
+    let (cx, cy) = (center.x as f64, center.y as f64);
+
+    frame.render_widget(
+        Canvas::default()
+            .x_bounds([0.0, right])
+            .y_bounds([0.0, top])
+            .marker(theme.marker)
+            .paint(|ctx| {
+                ctx.draw(&Painted::new(&arena.walls, theme.walls));
+
+                ctx.layer();
+
+                if arena.state != ArenaState::Startup && followed.is_none() {
+                    ctx.print(3.0, top - 4.0, scoreboard(arena));
+                }
+
+                ctx.layer();
+
+                match arena.state {
+                    ArenaState::Running | ArenaState::Paused | ArenaState::RoundOver { .. } => {
+                        for (i, player) in arena.players.iter().enumerate() {
+                            if player.alive {
+                                ctx.draw(&Painted::new(&player.snake, player_color(i)));
+                            }
+                        }
+                        if let Some(point) = &arena.point {
+                            ctx.draw(&Painted::new(point, theme.food));
+                        }
+                        if let Some(i) = followed.filter(|&i| arena.players[i].alive) {
+                            let head = arena.players[i].snake.head();
+
+                            ctx.print(
+                                head.x as f64 + 1.0,
+                                head.y as f64 + 1.0,
+                                Span::styled(
+                                    format!("P{}", i + 1),
+                                    Style::default()
+                                        .fg(player_color(i))
+                                        .add_modifier(Modifier::BOLD),
+                                ),
+                            );
+                        }
+
+                        match arena.state {
+                            ArenaState::Paused => {
+                                ctx.print(cx - 13.0, top - 8.0, "Paused, press P to resume");
+                            }
+                            ArenaState::RoundOver { winner } => {
+                                let outcome = match winner {
+                                    Some(winner) => Span::styled(
+                                        format!("Player {} wins the round", winner + 1),
+                                        Style::default().fg(player_color(winner)),
+                                    ),
+                                    None => Span::raw("Nobody wins the round"),
+                                };
+                                ctx.print(cx - 12.0, cy, outcome);
+                            }
+                            _ => {}
+                        }
+                    }
+                    ArenaState::MatchOver { winner } => {
+                        let score = arena
+                            .players
+                            .iter()
+                            .map(|player| player.wins.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" to ");
+
+                        ctx.draw(&Word::new("winner".to_string(), cx - 21.0, cy));
+                        ctx.print(
+                            cx - 14.0,
+                            cy - 5.0,
+                            Span::styled(
+                                format!("Player {} wins the match {score}", winner + 1),
+                                Style::default().fg(player_color(winner)),
+                            ),
+                        );
+                    }
+                    ArenaState::Startup => {
+                        ctx.draw(&Word::new("versus".to_string(), cx - 21.0, cy));
+                    }
+                }
+            }),
+        area,
+    )
+}
+
+/// How wide the side panel of the spectators is, in characters.
+const PANEL_WIDTH: u16 = 34;
+
+/// Renders the match on `arena` for a spectator following `followed`: the
+/// board, and the round, its time and every player on a side panel.
+/// `connected` tells which players are in the match.
+pub fn draw_spectator(
+    frame: &mut Frame,
+    arena: &Arena,
+    theme: &Theme,
+    followed: usize,
+    connected: &[bool],
+) {
+    let areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(PANEL_WIDTH)])
+        .split(frame.size());
+    let (board, panel) = (areas[0], areas[1]);
+
+    render_arena(frame, board, arena, theme, Some(followed));
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" Round {} ", arena.round));
+    let inner = block.inner(panel);
+    frame.render_widget(block, panel);
+
+    let timer = Rect::new(inner.x, inner.y, inner.width, 3.min(inner.height));
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(format!("Time {}", duration(arena.elapsed_ms / 1000))),
+            Line::from(format!("First to {} wins", arena.rounds_to_win)),
+        ]),
+        timer,
+    );
+
+    let rows = arena.players.iter().enumerate().map(|(i, player)| {
+        let status = if !connected.get(i).copied().unwrap_or(true) {
+            "away"
+        } else if player.alive {
+            "alive"
+        } else {
+            "out"
+        };
+        let style = if i == followed {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+
+        Row::new(vec![
+            Span::styled(format!("P{}", i + 1), Style::default().fg(player_color(i))),
+            Span::raw(player.score.to_string()),
+            Span::raw(player.snake.len().to_string()),
+            Span::raw(player.wins.to_string()),
+            Span::raw(status),
+        ])
+        .style(style)
+    });
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Length(5),
+        Constraint::Length(6),
+        Constraint::Length(5),
+        Constraint::Length(6),
+    ];
+
+    frame.render_widget(
+        Table::new(rows, widths).header(
+            Row::new(vec!["", "Food", "Length", "Wins", ""])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        ),
+        Rect::new(
+            inner.x,
+            timer.bottom(),
+            inner.width,
+            inner.height.saturating_sub(timer.height),
+        ),
+    );
+}
+
+/// The colour of the snake of `player`.
+pub fn player_color(player: usize) -> Color {
+    PLAYER_COLORS[player % PLAYER_COLORS.len()]
+}
+
+/// The foods eaten this round and the rounds won by every player, each in
+/// their colour, and the round being played.
+fn scoreboard(arena: &Arena) -> Line<'static> {
+    let mut spans: Vec<Span> = arena
+        .players
+        .iter()
+        .enumerate()
+        .map(|(i, player)| {
+            let style = Style::default().fg(player_color(i));
+            let style = if player.alive {
+                style
+            } else {
+                style.add_modifier(Modifier::CROSSED_OUT)
+            };
+
+            Span::styled(
+                format!("P{}: {} ({} won)   ", i + 1, player.score, player.wins),
+                style,
+            )
+        })
+        .collect();
+    spans.push(Span::raw(format!(
+        "Round {}, first to {} wins",
+        arena.round, arena.rounds_to_win
+    )));
+
+    Line::from(spans)
+}
+
+/// A running power-up as its name and a bar emptying as it runs out, e.g.
+/// `Magnet ██████░░░░`.
+fn countdown(power_up: PowerUp, remaining: f64) -> String {
+    let full = (remaining * POWER_UP_BAR as f64).ceil() as usize;
+
+    format!(
+        "{:<7} {}{}",
+        power_up.name(),
+        "█".repeat(full.min(POWER_UP_BAR)),
+        "░".repeat(POWER_UP_BAR.saturating_sub(full))
+    )
+}
+
+/// Renders `status` on the last line of the frame, over the board.
+pub fn draw_status(frame: &mut Frame, status: &str) {
+    let area = frame.size();
+    let line = Rect::new(area.x, area.bottom().saturating_sub(1), area.width, 1);
+
+    frame.render_widget(Clear, line);
+    frame.render_widget(
+        Paragraph::new(status).style(Style::default().fg(Color::Black).bg(Color::White)),
+        line,
+    );
+}
+
+/// Renders the high score table under the title of the startup screen.
+pub fn draw_high_scores(frame: &mut Frame, high_scores: &HighScores) {
+    let area = frame.size();
+    let top = area.height / 2 + 4;
+    let height = (high_scores.scores.len() as u16 + 2).min(area.height.saturating_sub(top + 1));
+    let width = 72.min(area.width.saturating_sub(2));
+    let table_area = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + top,
+        width,
+        height,
+    );
+
+    if high_scores.scores.is_empty() {
+        frame.render_widget(
+            Paragraph::new("No high scores yet").alignment(Alignment::Center),
+            table_area,
+        );
+        return;
+    }
+
+    let rows = high_scores.scores.iter().enumerate().map(|(i, score)| {
+        Row::new(vec![
+            format!("{}.", i + 1),
+            score.name.clone(),
+            score.score.to_string(),
+            score.length.to_string(),
+            duration(score.duration),
+            score.date.format("%Y-%m-%d").to_string(),
+            score.mode.name().to_string(),
+            score.seed.to_string(),
+        ])
+    });
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Length(16),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(10),
+        Constraint::Length(8),
+        Constraint::Min(4),
+    ];
+
+    frame.render_widget(
+        Table::new(rows, widths).header(
+            Row::new(vec![
+                "", "Name", "Score", "Length", "Time", "Date", "Mode", "Seed",
+            ])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+        ),
+        table_area,
+    );
+}
+
+/// Renders the key remapping screen in a box in the middle of the frame,
+/// with the outcome of the last change on the status line.
+pub fn draw_remap(frame: &mut Frame, remapper: &Remapper, bindings: &KeyBindings) {
+    let area = frame.size();
+    let height = (Action::ALL.len() as u16 + 4).min(area.height);
+    let width = 48.min(area.width);
+    let box_area = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let rows = Action::ALL.iter().enumerate().map(|(i, action)| {
+        let keys = bindings
+            .keys(*action)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let row = Row::new(vec![action.name().to_string(), keys]);
+
+        if i == remapper.selected {
+            row.style(Style::default().add_modifier(Modifier::REVERSED))
+        } else {
+            row
+        }
+    });
+    let widths = [Constraint::Length(10), Constraint::Min(10)];
+
+    frame.render_widget(Clear, box_area);
+    frame.render_widget(
+        Table::new(rows, widths)
+            .header(
+                Row::new(vec!["Action", "Keys"])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+            )
+            .block(Block::default().borders(Borders::ALL).title(" Keys ")),
+        box_area,
+    );
+
+    let status = match &remapper.message {
+        Some(message) => format!(" {message}"),
+        None => " up/down select  enter add a key  backspace clear  esc save and leave".to_string(),
+    };
+    draw_status(frame, &status);
+}
+
+/// Renders the stages of the campaign, which ones are cleared or locked and
+/// the best results on them, `selected` being highlighted.
+pub fn draw_level_select(
+    frame: &mut Frame,
+    stages: &[Stage],
+    progress: &Progress,
+    selected: usize,
+) {
+    let area = frame.size();
+    let height = (stages.len() as u16 + 4).min(area.height);
+    let width = 72.min(area.width);
+    let box_area = Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    );
+
+    let rows = stages.iter().enumerate().map(|(i, stage)| {
+        let record = progress.record(stage);
+        let state = if progress.is_cleared(stage) {
+            "cleared"
+        } else if progress.is_unlocked(stages, i) {
+            ""
+        } else {
+            "locked"
+        };
+        let row = Row::new(vec![
+            format!("{}.", i + 1),
+            stage.level.name.clone(),
+            stage
+                .goal()
+                .map(|goal| goal.to_string())
+                .unwrap_or_default(),
+            stage.par.to_string(),
+            record.map_or_else(String::new, |record| record.best_score.to_string()),
+            record
+                .and_then(|record| record.best_time)
+                .map_or_else(String::new, duration),
+            state.to_string(),
+        ]);
+
+        if i == selected {
+            row.style(Style::default().add_modifier(Modifier::REVERSED))
+        } else {
+            row
+        }
+    });
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Length(10),
+        Constraint::Length(20),
+        Constraint::Length(6),
+        Constraint::Length(5),
+        Constraint::Length(5),
+        Constraint::Min(7),
+    ];
+
+    frame.render_widget(Clear, box_area);
+    frame.render_widget(
+        Table::new(rows, widths)
+            .header(
+                Row::new(vec!["", "Level", "Goal", "Par", "Best", "Time", ""])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+            )
+            .block(Block::default().borders(Borders::ALL).title(" Campaign ")),
+        box_area,
+    );
+
+    draw_status(frame, " up/down select  enter play  q quit");
+}
+
+/// Formats a number of seconds as `m:ss`.
+pub fn duration(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Renders the level being edited on the whole frame: its walls, where the
+/// food spawns, the start of the snake and the cursor, with the state of the
+/// editor on the status line.
+pub fn draw_editor(frame: &mut Frame, editor: &Editor, theme: &Theme) {
+    let board = editor.board;
+    let walls = Walls::from_cells(board, editor.walls().copied());
+    // a short snake shows which way it starts
+    let snake = Snake::new(editor.spawn, editor.direction, 3);
+    let food: Vec<(f64, f64)> = editor
+        .food()
+        .map(|cell| (cell.x as f64, cell.y as f64))
+        .collect();
+    let cursor = editor.cursor;
+
+    frame.render_widget(
+        Canvas::default()
+            .x_bounds([0.0, (board.width - 1) as f64])
+            .y_bounds([0.0, (board.height - 1) as f64])
+            .marker(theme.marker)
+            .paint(|ctx| {
+                ctx.draw(&Points {
+                    coords: &food,
+                    color: FOOD_ZONE_COLOR,
+                });
+                ctx.draw(&Painted::new(&walls, theme.walls));
+                ctx.draw(&Painted::new(&snake, theme.snake));
+
+                ctx.layer();
+
+                ctx.draw(&Points {
+                    coords: &[(cursor.x as f64, cursor.y as f64)],
+                    color: CURSOR_COLOR,
+                });
+            }),
+        frame.size(),
+    );
+
+    let status = match &editor.message {
+        Some(message) => format!(" {message}"),
+        None => format!(
+            " {}{}  {} brush{}  {},{}  |  arrows move  space paint  w/f/e brush  d draw  p start  \
+             r rotate  t test  s save  q quit",
+            editor.name,
+            if editor.modified { "*" } else { "" },
+            editor.brush,
+            if editor.drawing { " (drawing)" } else { "" },
+            cursor.x,
+            cursor.y
+        ),
+    };
+    draw_status(frame, &status);
+}
+
+/// The cell of `board` drawn at `column` and `row` of `area` by a canvas
+/// with the marker of `theme`, to tell what was clicked. There is none when
+/// the board is too small for every character to show a cell.
+pub fn cell_at(area: Rect, board: Board, theme: &Theme, column: u16, row: u16) -> Option<Cell> {
+    if !area.contains((column, row).into()) {
+        return None;
+    }
+
+    let (cells_per_column, cells_per_row) = theme.cells_per_char();
+    let (column, row) = (column - area.x, row - area.y);
+    // the dot of the canvas grid a coordinate is drawn on, like the canvas
+    // does it
+    let dot = |value: i32, size: i32, resolution: f64| {
+        (value as f64 * (resolution - 1.0) / (size - 1).max(1) as f64) as u16
+    };
+    let resolution = (
+        (area.width * cells_per_column) as f64,
+        (area.height * cells_per_row) as f64,
+    );
+
+    let x = (0..board.width)
+        .find(|&x| dot(x, board.width, resolution.0) / cells_per_column == column)?;
+    let y = (0..board.height)
+        .rev()
+        .find(|&y| dot(board.height - 1 - y, board.height, resolution.1) / cells_per_row == row)?;
+
+    Some(Cell::new(x, y))
+}
@@ -1,55 +1,75 @@
-use collision_detection::{coord::Coord, Collidable};
+use std::collections::HashSet;
+
 use ratatui::{
     style::Color,
-    widgets::canvas::{Line, Shape},
+    widgets::canvas::{Points, Shape},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    grid::{Board, Cell},
+    ui::Painted,
 };
 
+/// The obstacles of the board, touching them ends the game. Leaving the
+/// board does too, unless it wraps around.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Walls {
-    pub corners: [Coord; 4],
+    pub board: Board,
+    cells: HashSet<Cell>,
 }
 
 impl Walls {
-    pub fn new(width: f64, height: f64) -> Self {
+    /// The rectangle surrounding the board, none if the board wraps around.
+    pub fn new(board: Board) -> Self {
+        let cells = if board.wraps {
+            HashSet::new()
+        } else {
+            (0..board.width)
+                .flat_map(|x| (0..board.height).map(move |y| Cell::new(x, y)))
+                .filter(|&cell| board.is_edge(cell))
+                .collect()
+        };
+
+        Walls { board, cells }
+    }
+
+    /// Walls on `cells`, e.g. the ones of a [`crate::level::Level`].
+    pub fn from_cells(board: Board, cells: impl IntoIterator<Item = Cell>) -> Self {
         Walls {
-            corners: [
-                Coord::new(width / 2.0, height),
-                Coord::new(width / 2.0, -height),
-                Coord::new(-width / 2.0, -height),
-                Coord::new(-width / 2.0, height),
-            ],
+            board,
+            cells: cells.into_iter().collect(),
         }
     }
+
+    pub fn contains(&self, cell: Cell) -> bool {
+        !self.board.contains(cell) || self.cells.contains(&cell)
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = &Cell> {
+        self.cells.iter()
+    }
 }
 
 impl Shape for Walls {
     fn draw(&self, painter: &mut ratatui::widgets::canvas::Painter) {
-        for i in 0..self.corners.len() {
-            let next: usize = (i + 1) % self.corners.len();
-
-            let line = Line {
-                x1: self.corners[i].x,
-                y1: self.corners[i].y,
-                x2: self.corners[next].x,
-                y2: self.corners[next].y,
-                color: Color::White,
-            };
-
-            line.draw(painter);
-        }
+        Painted::new(self, Color::White).draw(painter);
     }
 }
 
-impl Collidable for Walls {
-    fn get_border(&self) -> Vec<Coord> {
-        let mut borders = self.corners.to_vec();
-        borders.push(self.corners[0].clone());
-
-        borders
+impl Shape for Painted<'_, Walls> {
+    fn draw(&self, painter: &mut ratatui::widgets::canvas::Painter) {
+        let coords: Vec<(f64, f64)> = self
+            .item
+            .cells
             .iter()
-            .map(|coord| Coord {
-                x: coord.x.round(),
-                y: coord.y.round(),
-            })
-            .collect()
+            .map(|cell| (cell.x as f64, cell.y as f64))
+            .collect();
+
+        Points {
+            coords: &coords,
+            color: self.color,
+        }
+        .draw(painter);
     }
 }